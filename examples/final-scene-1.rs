@@ -1,7 +1,11 @@
+use std::sync::Arc;
+
 use itertools::iproduct;
 use rand::{Rng, SeedableRng};
 use ray_tow::camera::Camera;
-use ray_tow::material::Material;
+use ray_tow::distributed::Coordinator;
+use ray_tow::material::{Dielectric, Lambertian, Material, Metal};
+use ray_tow::scene::Scene;
 use ray_tow::shapes::{sphere::Sphere, Shape};
 use ray_tow::vectors::{random_in_range, random_unit_vector};
 use ray_tow::{utils, Vec3};
@@ -12,9 +16,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let seed = 42;
     let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
     let mut world: Vec<Shape> = vec![];
-    let mat_ground = Material::Lambertian {
-        albedo: Vec3::new(0.5, 0.5, 0.5),
-    };
+    let mat_ground = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
     world.push(Shape::Sphere(Sphere::new(
         Vec3::new(0., -1000., 0.),
         1000.,
@@ -30,20 +32,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
 
         if (center - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
-            let mat = if choose_mat < 0.8 {
+            let mat: Arc<dyn Material> = if choose_mat < 0.8 {
                 // diffuse
                 let albedo = random_unit_vector(&mut rng) * random_unit_vector(&mut rng);
-                Material::Lambertian { albedo }
+                Arc::new(Lambertian::new(albedo))
             } else if choose_mat < 0.95 {
                 // metal
                 let albedo = random_in_range(0.5..1., &mut rng);
                 let fuzz = rng.gen_range(0.0..0.5);
-                Material::Metal { albedo, fuzz }
+                Arc::new(Metal::new(albedo, fuzz))
             } else {
                 // glass
-                Material::Dielectric {
-                    index_of_refraction: 1.5,
-                }
+                Arc::new(Dielectric::new(1.5))
             };
 
             Some(Shape::Sphere(Sphere::new(center, 0.2, mat)))
@@ -57,27 +57,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     world.push(Shape::Sphere(Sphere::new(
         Vec3::new(0., 1., 0.),
         1.,
-        Material::Dielectric {
-            index_of_refraction: 1.5,
-        },
+        Arc::new(Dielectric::new(1.5)),
     )));
     world.push(Shape::Sphere(Sphere::new(
         Vec3::new(-4., 1., 0.),
         1.,
-        Material::Lambertian {
-            albedo: Vec3::new(0.4, 0.2, 0.1),
-        },
+        Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1))),
     )));
     world.push(Shape::Sphere(Sphere::new(
         Vec3::new(4., 1., 0.),
         1.,
-        Material::Metal {
-            albedo: Vec3::new(0.7, 0.6, 0.5),
-            fuzz: 0.,
-        },
+        Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.)),
     )));
 
-    let camera = Camera::init()
+    let camera_builder = Camera::init()
         .position(Vec3::new(13., 2., 3.))
         .look_at(Vec3::new(0., 0., 0.))
         .up(Vec3::Y)
@@ -91,11 +84,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // .max_depth(50)
         // .vfov(20.)
         .f_stop(0.6)
-        .focal_length(60e-3)
-        .build();
+        .focal_length(60e-3);
+
+    // Export the scene for the `ray-tow` renderer instead of rendering it, e.g.
+    // `cargo run --example final-scene-1 -- scenes/final-scene-1.toml`
+    if let Some(path) = std::env::args().nth(1) {
+        Scene::new(camera_builder, world).save(path)?;
+        return Ok(());
+    }
+
+    let camera = camera_builder.build();
 
     println!("{:?}", camera);
-    let render_buffer = camera.render(&world);
+    // Render on worker processes if given, e.g. RAY_TOW_WORKERS=127.0.0.1:7878,127.0.0.1:7879
+    let render_buffer = match std::env::var("RAY_TOW_WORKERS") {
+        Ok(workers) => {
+            let workers = workers
+                .split(',')
+                .map(|addr| addr.trim().parse())
+                .collect::<Result<Vec<_>, _>>()?;
+            Coordinator::new(workers).render(&camera, &world)?
+        }
+        Err(_) => camera.render(&world),
+    };
 
     // Get timestamp for keeping a record of the ray tracer progress
     let timestamp = utils::timestamp();
@@ -0,0 +1,17 @@
+use std::net::TcpListener;
+
+use ray_tow::distributed;
+use ray_tow::shapes::Shape;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Address to listen on, e.g. `cargo run --release --example render-worker 127.0.0.1:7878`
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let listener = TcpListener::bind(addr)?;
+    println!("Listening on {}", listener.local_addr()?);
+
+    distributed::serve::<Vec<Shape>>(listener)?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Writes `scenes/smoke.vol`, a voxel grid of a rising smoke plume used by
+//! `scenes/heterogeneous.toml`. Simulation exporters can write the same format with
+//! `VoxelGrid::save`.
+
+use ray_tow::density::VoxelGrid;
+use ray_tow::texture::Perlin;
+use ray_tow::Vec3;
+
+const RESOLUTION: [u32; 3] = [48, 96, 48];
+
+fn main() -> std::io::Result<()> {
+    let perlin = Perlin::new(3);
+    let [nx, ny, nz] = RESOLUTION;
+
+    let mut densities = Vec::with_capacity((nx * ny * nz) as usize);
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                let point = Vec3::new(
+                    (x as f64 + 0.5) / nx as f64,
+                    (y as f64 + 0.5) / ny as f64,
+                    (z as f64 + 0.5) / nz as f64,
+                );
+
+                // A column that sways and spreads out as it rises, breaking up into wisps
+                let height = point.y;
+                let center = Vec3::new(
+                    0.5 + 0.12 * (5. * height).sin() * height,
+                    height,
+                    0.5 + 0.08 * (3. * height).cos() * height,
+                );
+                let radius = 0.1 + 0.35 * height;
+                let distance = (point - center).length() / radius;
+                let wisps = perlin.turbulence(Vec3::new(6., 3., 6.) * point, 4);
+                let density = (1. - distance + 0.8 * wisps - 0.3).clamp(0., 1.) * (1. - height);
+                densities.push(density as f32);
+            }
+        }
+    }
+
+    VoxelGrid::save("scenes/smoke.vol", RESOLUTION, &densities)?;
+    println!("Saved to scenes/smoke.vol");
+    Ok(())
+}
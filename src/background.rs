@@ -0,0 +1,46 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ray::Ray;
+use crate::sky::Sky;
+use crate::Color;
+
+/// Color of rays that escape the scene without hitting anything.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Background {
+    Solid {
+        color: Color,
+    },
+    /// Vertical blend from `bottom` (looking straight down) to `top` (looking straight up)
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// Daylight sky, whose sun the camera adds to its lights
+    Sky(Sky),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::ONE,
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Solid { color } => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = ray.direction.normalize();
+                let t = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - t) * *bottom + t * *top
+            }
+            Background::Sky(sky) => sky.radiance(ray.direction.normalize()),
+        }
+    }
+}
@@ -1,15 +1,28 @@
 use indicatif::ParallelProgressIterator;
 use itertools::iproduct;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
-
-use crate::hit_record::Hittable;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::background::Background;
+use crate::hit_record::{FaceSide, HitRecord, Hittable};
+use crate::light::{power_heuristic, Light, LightSample, Lights};
+use crate::material::Material;
+use crate::medium::{
+    ratio_tracking, sample_flight, sample_phase, track_flight, transmittance, Flight, Fog, Medium,
+    MediumStack,
+};
 use crate::raw_image_buffer::RawImageBuffer;
 use crate::ray::Ray;
+use crate::spectrum::Wavelengths;
 use crate::vectors::{random_in_unit_disc, sample_square};
 use crate::{Color, Vec3};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Camera {
     pub position: Vec3,
     // pub direction: Vec3,
@@ -26,10 +39,50 @@ pub struct Camera {
     pixel00_loc: Vec3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
+    /// Angle covered by one pixel, for texture filtering
+    pixel_spread: f64,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
     // defocus_angle: f64,
     f_stop: Option<f64>,
+    seed: u64,
+    /// Trace wavelengths rather than RGB
+    spectral: bool,
+    pub background: Background,
+    /// Fog filling the world, which every ray starts in
+    pub fog: Option<Fog>,
+    /// Lights without geometry, sampled along with the emissive shapes in the world
+    pub lights: Vec<Light>,
+}
+
+/// A rectangular region of the image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Number of pixels covered by the tile.
+    pub fn len(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl Camera {
@@ -60,20 +113,12 @@ impl Camera {
         //         pb.inc(1);
         //     }
         // }
+        let lights = Lights::new(world.lights(), &self.lights);
         let xys: Vec<_> = iproduct!(0..self.image_height, 0..self.image_width).collect();
         let colors: Vec<Color> = xys
             .par_iter()
             .progress_count(xys.len() as u64)
-            .map(|(y, x)| {
-                let mut pixel_color = Color::ZERO;
-
-                for _sample_n in 0..self.samples_per_pixel {
-                    let ray = self.create_ray(*x, *y);
-                    pixel_color += Camera::ray_color(&ray, self.max_depth, world);
-                }
-
-                pixel_color / self.samples_per_pixel as f64
-            })
+            .map(|(y, x)| self.pixel_color(*x, *y, 0, world, &lights))
             .collect();
 
         colors
@@ -93,7 +138,98 @@ impl Camera {
         rawbuf
     }
 
-    fn ray_color<T>(ray: &Ray, depth: u32, world: &T) -> Color
+    /// Render a single tile of the image, returning the linear colors of its pixels in row-major
+    /// order. Pixels are seeded individually, so a tile renders identically to the same region of
+    /// `render`.
+    pub fn render_tile<T>(&self, world: &T, tile: &Tile) -> Vec<Color>
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        let lights = Lights::new(world.lights(), &self.lights);
+        let xys: Vec<_> =
+            iproduct!(tile.y..tile.y + tile.height, tile.x..tile.x + tile.width).collect();
+        xys.par_iter()
+            .map(|(y, x)| self.pixel_color(*x, *y, 0, world, &lights))
+            .collect()
+    }
+
+    /// Render one pass of `samples_per_pixel` samples for progressive rendering, returning linear
+    /// colors in row-major order. Each pass uses fresh random streams, so averaging passes
+    /// converges like a single render with more samples. Pass 0 matches `render`.
+    ///
+    /// Returns `None` as soon as `cancel` is set.
+    pub fn render_pass<T>(&self, world: &T, pass: u32, cancel: &AtomicBool) -> Option<Vec<Color>>
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        let lights = Lights::new(world.lights(), &self.lights);
+        let xys: Vec<_> = iproduct!(0..self.image_height, 0..self.image_width).collect();
+        xys.par_iter()
+            .map(|(y, x)| {
+                if cancel.load(Ordering::Relaxed) {
+                    None
+                } else {
+                    Some(self.pixel_color(*x, *y, pass, world, &lights))
+                }
+            })
+            .collect()
+    }
+
+    /// Split the image into tiles of at most `size` by `size` pixels, in row-major order.
+    pub fn tiles(&self, size: u32) -> Vec<Tile> {
+        let size = size.max(1);
+        iproduct!(
+            (0..self.image_height).step_by(size as usize),
+            (0..self.image_width).step_by(size as usize)
+        )
+        .map(|(y, x)| {
+            Tile::new(
+                x,
+                y,
+                size.min(self.image_width - x),
+                size.min(self.image_height - y),
+            )
+        })
+        .collect()
+    }
+
+    fn pixel_color<T>(&self, x: u32, y: u32, pass: u32, world: &T, lights: &Lights) -> Color
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        let mut rng = self.pixel_rng(x, y, pass);
+        let mut pixel_color = Color::ZERO;
+
+        for _sample_n in 0..self.samples_per_pixel {
+            let ray = self.create_ray(x, y, &mut rng);
+            let radiance = self.ray_color(&ray, self.max_depth, world, lights, &mut rng);
+            pixel_color += match ray.wavelengths {
+                Some(wavelengths) => wavelengths.to_rgb(radiance),
+                None => radiance,
+            };
+        }
+
+        pixel_color / self.samples_per_pixel as f64
+    }
+
+    fn pixel_rng(&self, x: u32, y: u32, pass: u32) -> StdRng {
+        // Give every pixel its own random stream so the result doesn't depend on render order
+        let index = y as u64 * self.image_width as u64 + x as u64;
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        seed[8..16].copy_from_slice(&index.to_le_bytes());
+        seed[16..20].copy_from_slice(&pass.to_le_bytes());
+        StdRng::from_seed(seed)
+    }
+
+    fn ray_color<T>(
+        &self,
+        ray: &Ray,
+        depth: u32,
+        world: &T,
+        lights: &Lights,
+        rng: &mut impl Rng,
+    ) -> Color
     where
         T: Hittable + std::marker::Sync,
     {
@@ -101,26 +237,243 @@ impl Camera {
             return Color::ZERO;
         }
 
-        if let Some(hit_record) = world.hit(ray, 0.001..f64::INFINITY) {
-            if let Some((scattered_ray, attenuation)) =
-                hit_record.material.scatter(ray, &hit_record)
-            {
-                attenuation * Camera::ray_color(&scattered_ray, depth - 1, world)
+        let hit_record = world.hit(ray, 0.001..f64::INFINITY);
+        // Media end where the scene does, so rays leaving it see the background through no fog
+        let distance = hit_record
+            .as_ref()
+            .map_or(0., |hit_record| hit_record.t * ray.direction.length());
+
+        // Light is absorbed on its way through the medium to the hit, and may scatter off it first
+        let transmittance = match ray.media.current() {
+            Some(medium) if medium.scatters() => {
+                let absorption = at_wavelengths(ray, medium.absorption);
+                let scattering = at_wavelengths(ray, medium.scattering);
+                let flight = match &medium.density {
+                    Some(density) => track_flight(
+                        density,
+                        ray.origin,
+                        ray.direction.normalize(),
+                        absorption,
+                        scattering,
+                        distance,
+                        rng,
+                    ),
+                    None => sample_flight(absorption, scattering, distance, rng),
+                };
+                match flight {
+                    Flight::Scattered { distance, weight } => {
+                        let t = distance / ray.direction.length();
+                        let direction = sample_phase(ray.direction, medium.anisotropy, rng);
+                        let mut scattered_ray = Ray::new(ray.at(t), direction);
+                        scattered_ray.width = ray.footprint(t);
+                        scattered_ray.spread = ray.spread;
+                        scattered_ray.media = ray.media.clone();
+                        scattered_ray.wavelengths = ray.wavelengths;
+                        return weight
+                            * self.ray_color(&scattered_ray, depth - 1, world, lights, rng);
+                    }
+                    Flight::Passed { weight } => weight,
+                    Flight::Absorbed => return Color::ZERO,
+                }
+            }
+            Some(Medium {
+                density: Some(density),
+                absorption,
+                ..
+            }) => ratio_tracking(
+                density,
+                ray.origin,
+                ray.direction.normalize(),
+                at_wavelengths(ray, *absorption),
+                distance,
+                rng,
+            ),
+            _ => transmittance(at_wavelengths(ray, ray.media.absorption()), distance),
+        };
+
+        if let Some(mut hit_record) = hit_record {
+            if let Some(through) = pass_through(ray, &hit_record) {
+                return transmittance * self.ray_color(&through, depth - 1, world, lights, rng);
+            }
+
+            // Materials are told what is on the other side of their surface, from the media the
+            // ray has travelled into
+            let medium = hit_record.material.medium();
+            let id = material_id(hit_record.material);
+            let entering = hit_record.face_side == FaceSide::Front;
+            hit_record.exterior_ior = match &medium {
+                None => ray.media.ior(ray.wavelength()),
+                // Surfaces that aren't passed through are seen, so there is an outside
+                Some(medium) => ray
+                    .media
+                    .exterior_ior(id, medium, entering, ray.wavelength())
+                    .unwrap_or(1.),
+            };
+
+            let mut emitted = at_wavelengths(ray, hit_record.material.emitted(ray, &hit_record));
+            if let Some(scatter_pdf) = ray.scatter_pdf {
+                // Sampling the lights at the last bounce could have found this light too
+                if emitted != Color::ZERO {
+                    let light_pdf = lights.pdf(ray.origin, ray.direction);
+                    emitted *= power_heuristic(scatter_pdf, light_pdf);
+                }
+            }
+
+            // Light passing through transmissive surfaces is left for scattering to find
+            let samples_lights = medium.is_none() && !lights.is_empty();
+            let direct = if samples_lights {
+                self.sample_light(ray, &hit_record, world, lights, rng)
             } else {
                 Color::ZERO
+            };
+
+            if let Some((mut scattered_ray, attenuation)) =
+                hit_record.material.scatter(ray, &hit_record, rng)
+            {
+                let mut attenuation = at_wavelengths(ray, attenuation);
+                // Keep widening the cone through bounces, so textures seen in reflections blur too
+                scattered_ray.width = hit_record.footprint;
+                scattered_ray.spread = ray.spread;
+                scattered_ray.media = ray.media.clone();
+                scattered_ray.wavelengths = ray.wavelengths;
+                if samples_lights {
+                    // Specular bounces have no density, and light sampling can't find them
+                    let pdf = hit_record.material.pdf(ray, &hit_record, &scattered_ray);
+                    scattered_ray.scatter_pdf = (pdf > 0.).then_some(pdf);
+                }
+                if let Some(medium) = medium {
+                    // Dispersion sends each wavelength its own way, so only the hero wavelength
+                    // can follow this path
+                    let dispersive = medium.ior.is_dispersive()
+                        || ray
+                            .media
+                            .current()
+                            .is_some_and(|current| current.ior.is_dispersive());
+                    if let Some(wavelengths) = &mut scattered_ray.wavelengths {
+                        if dispersive {
+                            attenuation *= wavelengths.terminate_secondary();
+                        }
+                    }
+                    if scattered_ray.direction.dot(hit_record.geometric_normal) < 0. {
+                        scattered_ray.media.cross(id, medium, entering);
+                    }
+                }
+                transmittance
+                    * (emitted
+                        + direct
+                        + attenuation
+                            * self.ray_color(&scattered_ray, depth - 1, world, lights, rng))
+            } else {
+                transmittance * (emitted + direct)
             }
             // let ray = Ray::new(hit_record.point, direction);
             // 0.5 * Camera::ray_color(&ray, depth - 1, world)
         } else {
-            let unit_direction = ray.direction.normalize();
-            let t = 0.5 * (unit_direction.y + 1.0);
-            (1.0 - t) * Color::ONE + t * Color::new(0.5, 0.7, 1.0)
+            let mut sun = at_wavelengths(ray, lights.sun_radiance(ray.direction));
+            if let Some(scatter_pdf) = ray.scatter_pdf {
+                if sun != Color::ZERO {
+                    sun *= power_heuristic(scatter_pdf, lights.pdf(ray.origin, ray.direction));
+                }
+            }
+            at_wavelengths(ray, self.background.color(ray)) + sun
         }
     }
 
-    fn create_ray(&self, x: u32, y: u32) -> Ray {
-        let mut rng = thread_rng();
-        let offset = sample_square(&mut rng);
+    /// Light arriving at a surface straight from a light picked at random, weighed against finding
+    /// it by scattering.
+    fn sample_light<T>(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        world: &T,
+        lights: &Lights,
+        rng: &mut impl Rng,
+    ) -> Color
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        let Some(sample) = lights.sample(hit_record.point, rng) else {
+            return Color::ZERO;
+        };
+        let direction = match sample {
+            LightSample::Direction(direction) => direction,
+            LightSample::Delta { direction, .. } => direction,
+        };
+        let mut shadow_ray = Ray::new(hit_record.point, direction);
+        shadow_ray.media = ray.media.clone();
+        shadow_ray.wavelengths = ray.wavelengths;
+
+        let value = at_wavelengths(ray, hit_record.material.eval(ray, hit_record, &shadow_ray));
+        if value == Color::ZERO {
+            return Color::ZERO;
+        }
+
+        // Shadow rays carry on through surfaces that aren't seen, like those of volumes, dimmed
+        // by what they pass through. Anything else in the way casts a shadow.
+        let max_t = match sample {
+            LightSample::Direction(_) => f64::INFINITY,
+            LightSample::Delta { distance, .. } => distance,
+        };
+        let mut travelled = 0.;
+        let mut crossings = 0;
+        let mut passed = Color::ONE;
+        let light_hit = loop {
+            let Some(hit) = world.hit(&shadow_ray, 0.001..max_t - travelled) else {
+                break None;
+            };
+            match pass_through(&shadow_ray, &hit) {
+                Some(through) if crossings < self.max_depth => {
+                    let distance = hit.t * direction.length();
+                    passed *= unscattered_transmittance(&shadow_ray, distance, rng);
+                    travelled += hit.t;
+                    crossings += 1;
+                    shadow_ray = through;
+                }
+                _ => break Some(hit),
+            }
+        };
+        let (emitted, distance) = match (sample, light_hit) {
+            (LightSample::Direction(_), Some(light_hit)) => (
+                light_hit.material.emitted(&shadow_ray, &light_hit),
+                light_hit.t * direction.length(),
+            ),
+            // Media end where the scene does, as for rays leaving it
+            (LightSample::Direction(_), None) => (lights.sun_radiance(direction), 0.),
+            (LightSample::Delta { .. }, Some(_)) => return Color::ZERO,
+            (
+                LightSample::Delta {
+                    distance, radiance, ..
+                },
+                None,
+            ) => {
+                let distance = if distance.is_finite() {
+                    distance - travelled
+                } else {
+                    0.
+                };
+                let transmittance = passed * unscattered_transmittance(&shadow_ray, distance, rng);
+                return value * at_wavelengths(ray, radiance) * transmittance;
+            }
+        };
+        if emitted == Color::ZERO {
+            return Color::ZERO;
+        }
+
+        let light_pdf = lights.pdf(hit_record.point, direction);
+        if light_pdf <= 0. {
+            return Color::ZERO;
+        }
+        let transmittance = passed * unscattered_transmittance(&shadow_ray, distance, rng);
+        let scatter_pdf = hit_record.material.pdf(ray, hit_record, &shadow_ray);
+        value
+            * at_wavelengths(ray, emitted)
+            * transmittance
+            * power_heuristic(light_pdf, scatter_pdf)
+            / light_pdf
+    }
+
+    fn create_ray(&self, x: u32, y: u32, rng: &mut impl Rng) -> Ray {
+        let offset = sample_square(rng);
 
         let pixel_sample = self.pixel00_loc
             + (x as f64 + offset.x) * self.pixel_delta_u
@@ -129,16 +482,28 @@ impl Camera {
         let origin = if self.f_stop.is_none() {
             self.position
         } else {
-            let defocus = random_in_unit_disc(&mut rng);
+            let defocus = random_in_unit_disc(rng);
             self.position + defocus.x * self.defocus_disk_u + defocus.y * self.defocus_disk_v
         };
         let direction = pixel_sample - origin;
-        Ray::new(origin, direction)
+        let mut ray = Ray::new(origin, direction);
+        ray.spread = self.pixel_spread;
+        if let Some(fog) = &self.fog {
+            ray.media = MediumStack::surrounded_by(fog.medium());
+        }
+        if self.spectral {
+            ray.wavelengths = Some(Wavelengths::sample(rng.gen()));
+        }
+        ray
     }
 }
 
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct CameraBuilder {
     image_width: u32,
+    image_height: Option<u32>,
     aspect_ratio: f64,
     position: Vec3,
     look_at: Vec3,
@@ -153,12 +518,21 @@ pub struct CameraBuilder {
     f_stop: Option<f64>,
     sensor_width: f64,
     sensor_height: f64,
+    /// Seed for the per-pixel random number generators
+    seed: u64,
+    /// Trace wavelengths rather than RGB, for dispersion. Slower and noisier.
+    spectral: bool,
+    background: Background,
+    fog: Option<Fog>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    lights: Vec<Light>,
 }
 
 impl Default for CameraBuilder {
     fn default() -> Self {
         Self {
             image_width: 400,
+            image_height: None,
             aspect_ratio: 16.0 / 9.0,
             position: Vec3::ZERO,
             look_at: Vec3::new(0., 0., -1.),
@@ -171,6 +545,11 @@ impl Default for CameraBuilder {
             f_stop: None,
             sensor_width: 36e-3,
             sensor_height: 24e-3,
+            seed: 0,
+            spectral: false,
+            background: Background::default(),
+            fog: None,
+            lights: Vec::new(),
         }
     }
 }
@@ -180,6 +559,13 @@ impl CameraBuilder {
         self
     }
 
+    /// Override the image height given by the sensor aspect ratio. The sensor height is cropped to
+    /// match, keeping pixels square.
+    pub fn image_height(mut self, image_height: u32) -> Self {
+        self.image_height = Some(image_height);
+        self
+    }
+
     // pub fn aspect_ratio(mut self, aspect_ratio: f64) -> Self {
     //     self.aspect_ratio = aspect_ratio;
     //     self
@@ -230,6 +616,34 @@ impl CameraBuilder {
         self
     }
 
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Fill the world with fog. The background is only seen through it, so it gives the fog its
+    /// light and color.
+    pub fn fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+
+    /// Add a light without geometry, such as a point light or the sun.
+    pub fn light(mut self, light: impl Into<Light>) -> Self {
+        self.lights.push(light.into());
+        self
+    }
+
     pub fn sensor_dimensions(mut self, width: f64, height: f64) -> Self {
         self.aspect_ratio = width / height;
         self.sensor_width = width;
@@ -237,9 +651,16 @@ impl CameraBuilder {
         self
     }
 
-    pub fn build(self) -> Camera {
+    pub fn build(mut self) -> Camera {
         // Calculate height
-        let image_height = (self.image_width as f64 / self.aspect_ratio) as u32;
+        let image_height = match self.image_height {
+            Some(image_height) => {
+                self.aspect_ratio = self.image_width as f64 / image_height as f64;
+                self.sensor_height = self.sensor_width / self.aspect_ratio;
+                image_height
+            }
+            None => (self.image_width as f64 / self.aspect_ratio) as u32,
+        };
         let image_height = if image_height < 1 { 1 } else { image_height };
 
         // let actual_aspect_ratio = self.image_width as f64 / image_height as f64;
@@ -279,14 +700,20 @@ impl CameraBuilder {
 
         // Calculate the defocus disk basis vectors
         // let aperture_radius = self.focal_length * (self.defocus_angle / 2.).to_radians().tan();
-        let aperture_radius = if self.f_stop.is_some() {
-            self.focal_length / (2. * self.f_stop.unwrap())
+        let aperture_radius = if let Some(f_stop) = self.f_stop {
+            self.focal_length / (2. * f_stop)
         } else {
             0.
         };
         let defocus_disk_u = aperture_radius * u;
         let defocus_disk_v = aperture_radius * v;
 
+        // The sky's sun is sampled like any other light
+        let mut lights = self.lights;
+        if let Background::Sky(sky) = &self.background {
+            lights.push(sky.sun().into());
+        }
+
         Camera {
             position: self.position,
             // pub direction: Vec3,
@@ -303,10 +730,75 @@ impl CameraBuilder {
             pixel00_loc,
             pixel_delta_u,
             pixel_delta_v,
+            pixel_spread: pixel_delta_u.length() / focal_dist,
             defocus_disk_u,
             defocus_disk_v,
             // defocus_angle: self.defocus_angle,
             f_stop: self.f_stop,
+            seed: self.seed,
+            spectral: self.spectral,
+            background: self.background,
+            fog: self.fog,
+            lights,
         }
     }
 }
+
+/// Identifies a material in a [`MediumStack`], so leaving it removes the right entry.
+fn material_id(material: &dyn Material) -> usize {
+    material as *const dyn Material as *const () as usize
+}
+
+/// The ray carrying straight on through the surface `ray` hit, if it isn't seen: the boundary of
+/// a volume, or a surface hidden inside a medium of higher priority.
+fn pass_through(ray: &Ray, hit_record: &HitRecord) -> Option<Ray> {
+    let medium = hit_record.material.medium()?;
+    let id = material_id(hit_record.material);
+    let entering = hit_record.face_side == FaceSide::Front;
+    let hidden = ray
+        .media
+        .exterior_ior(id, &medium, entering, ray.wavelength())
+        .is_none();
+    if !hidden && !hit_record.material.is_invisible() {
+        return None;
+    }
+
+    let mut through = Ray::new(hit_record.point, ray.direction);
+    through.width = hit_record.footprint;
+    through.spread = ray.spread;
+    through.media = ray.media.clone();
+    through.media.cross(id, medium, entering);
+    through.wavelengths = ray.wavelengths;
+    // Lights found beyond are weighed as if the surface weren't there
+    through.scatter_pdf = ray.scatter_pdf;
+    Some(through)
+}
+
+/// Fraction of light making it `distance` along `ray` through the medium it's in, neither absorbed
+/// nor scattered away.
+fn unscattered_transmittance(ray: &Ray, distance: f64, rng: &mut impl Rng) -> Color {
+    let Some(medium) = ray.media.current() else {
+        return Color::ONE;
+    };
+    let extinction = at_wavelengths(ray, medium.absorption + medium.scattering);
+    match &medium.density {
+        Some(density) => ratio_tracking(
+            density,
+            ray.origin,
+            ray.direction.normalize(),
+            extinction,
+            distance,
+            rng,
+        ),
+        None => transmittance(extinction, distance),
+    }
+}
+
+/// Colors from materials and backgrounds are RGB, but spectral rays need their values at the ray's
+/// wavelengths.
+fn at_wavelengths(ray: &Ray, rgb: Color) -> Color {
+    match ray.wavelengths {
+        Some(wavelengths) => wavelengths.upsample(rgb),
+        None => rgb,
+    }
+}
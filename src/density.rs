@@ -0,0 +1,275 @@
+//! Density fields for heterogeneous media, like smoke simulations and clouds.
+//!
+//! A field gives the density at points in the unit cube, which a
+//! [`HeterogeneousMedium`](crate::shapes::heterogeneous_medium::HeterogeneousMedium) stretches over
+//! its box. Fields can be voxel grids read from disk ([`VoxelGrid`]) or procedural
+//! ([`NoiseDensity`]).
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{Fractal, Perlin};
+use crate::Vec3;
+
+/// Density varying over the unit cube, usually between 0 and 1.
+///
+/// Like textures, fields are shared as `Arc<dyn DensityField>` and need `#[typetag::serde]` on
+/// their `impl DensityField` block with the `serde` feature.
+#[cfg_attr(feature = "serde", typetag::serde(tag = "type"))]
+pub trait DensityField: fmt::Debug + Send + Sync {
+    /// Density at `point`, which is inside the unit cube.
+    fn density(&self, point: Vec3) -> f64;
+
+    /// The highest density anywhere. Rays are tracked through the field in steps of this, so a
+    /// tight bound renders faster.
+    fn max_density(&self) -> f64;
+}
+
+/// Densities sampled on a regular grid of voxels, interpolated trilinearly between voxel centers.
+///
+/// Grid files are little-endian binary: the number of voxels along X, Y and Z as `u32`s, then
+/// one `f32` density per voxel, with X varying fastest and Z slowest.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "VoxelGridFile", into = "VoxelGridFile")
+)]
+pub struct VoxelGrid {
+    path: PathBuf,
+    resolution: [usize; 3],
+    densities: Arc<[f32]>,
+    max_density: f64,
+}
+
+impl VoxelGrid {
+    /// Load a grid file. Relative paths are relative to the working directory.
+    pub fn load<T>(path: T) -> io::Result<Self>
+    where
+        T: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let (header, data) = bytes
+            .split_at_checked(12)
+            .ok_or_else(|| invalid("missing grid dimensions".to_string()))?;
+        let resolution: [usize; 3] = std::array::from_fn(|axis| {
+            let bytes = header[4 * axis..4 * axis + 4].try_into().unwrap();
+            u32::from_le_bytes(bytes) as usize
+        });
+        let [nx, ny, nz] = resolution;
+        // Sizes from a corrupt header can overflow
+        let voxels = nx
+            .checked_mul(ny)
+            .and_then(|voxels| voxels.checked_mul(nz))
+            .filter(|voxels| voxels.checked_mul(4).is_some())
+            .ok_or_else(|| invalid(format!("a {nx}x{ny}x{nz} grid is too large")))?;
+        if voxels == 0 || data.len() != 4 * voxels {
+            return Err(invalid(format!(
+                "expected {voxels} densities for a {nx}x{ny}x{nz} grid, found {} bytes",
+                data.len()
+            )));
+        }
+
+        let densities: Arc<[f32]> = data
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        let max_density = densities
+            .iter()
+            .fold(0f32, |max, &density| max.max(density)) as f64;
+        Ok(Self {
+            path: path.to_path_buf(),
+            resolution,
+            densities,
+            max_density,
+        })
+    }
+
+    /// Write a grid file, for exporting simulations to. `densities` has X varying fastest.
+    pub fn save<T>(path: T, resolution: [u32; 3], densities: &[f32]) -> io::Result<()>
+    where
+        T: AsRef<Path>,
+    {
+        let mut bytes = Vec::with_capacity(12 + 4 * densities.len());
+        for size in resolution {
+            bytes.extend(size.to_le_bytes());
+        }
+        for density in densities {
+            bytes.extend(density.to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of voxels along each axis.
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    fn voxel(&self, [x, y, z]: [usize; 3]) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.densities[(z * ny + y) * nx + x] as f64
+    }
+}
+
+impl fmt::Debug for VoxelGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VoxelGrid")
+            .field("path", &self.path)
+            .field("resolution", &self.resolution)
+            .field("max_density", &self.max_density)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "grid"))]
+impl DensityField for VoxelGrid {
+    fn density(&self, point: Vec3) -> f64 {
+        // Voxel centers are at half-integer coordinates, and the edge voxels extend to the faces
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut t = [0.; 3];
+        for axis in 0..3 {
+            let size = self.resolution[axis];
+            let x = (point[axis] * size as f64 - 0.5).clamp(0., (size - 1) as f64);
+            lower[axis] = x as usize;
+            upper[axis] = (lower[axis] + 1).min(size - 1);
+            t[axis] = x - lower[axis] as f64;
+        }
+
+        let mut density = 0.;
+        for (dx, dy, dz) in itertools::iproduct!(0..2, 0..2, 0..2) {
+            let corner = [dx, dy, dz];
+            let mut voxel = [0; 3];
+            let mut weight = 1.;
+            for axis in 0..3 {
+                if corner[axis] == 0 {
+                    voxel[axis] = lower[axis];
+                    weight *= 1. - t[axis];
+                } else {
+                    voxel[axis] = upper[axis];
+                    weight *= t[axis];
+                }
+            }
+            density += weight * self.voxel(voxel);
+        }
+        density
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+/// How a voxel grid is stored in scene files: just the path, loaded on deserialization.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VoxelGridFile {
+    path: PathBuf,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<VoxelGridFile> for VoxelGrid {
+    type Error = String;
+
+    fn try_from(file: VoxelGridFile) -> Result<Self, Self::Error> {
+        VoxelGrid::load(&file.path)
+            .map_err(|err| format!("failed to load {}: {err}", file.path.display()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<VoxelGrid> for VoxelGridFile {
+    fn from(grid: VoxelGrid) -> Self {
+        Self { path: grid.path }
+    }
+}
+
+/// Billowing density from fractal noise, for clouds and smoke without a simulation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct NoiseDensity {
+    #[cfg_attr(feature = "serde", serde(rename = "seed"))]
+    pub perlin: Perlin,
+    /// Frequency of the noise over the unit cube: features are roughly `1 / scale` across
+    pub scale: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_octaves"))]
+    pub octaves: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fractal: Fractal,
+    /// Fade the density out towards the edges of the cube, so the volume looks like a billowing
+    /// puff rather than a box
+    #[cfg_attr(feature = "serde", serde(default = "default_fade"))]
+    pub fade: bool,
+}
+
+impl NoiseDensity {
+    pub fn new(seed: u64, scale: f64) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            octaves: default_octaves(),
+            fractal: Fractal::default(),
+            fade: default_fade(),
+        }
+    }
+
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn fractal(mut self, fractal: Fractal) -> Self {
+        self.fractal = fractal;
+        self
+    }
+
+    pub fn fade(mut self, fade: bool) -> Self {
+        self.fade = fade;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "noise"))]
+impl DensityField for NoiseDensity {
+    fn density(&self, point: Vec3) -> f64 {
+        let noise = self.scale * point;
+        let density = match self.fractal {
+            Fractal::Fbm => 0.5 + 0.5 * self.perlin.fbm(noise, self.octaves),
+            Fractal::Turbulence => self.perlin.turbulence(noise, self.octaves),
+        };
+        if self.fade {
+            // The noise eats into a ball inscribed in the cube, billowing out of its surface
+            let falloff = 1. - (2. * point - 1.).length_squared();
+            (2. * (density + falloff) - 2.).clamp(0., 1.)
+        } else {
+            density.clamp(0., 1.)
+        }
+    }
+
+    fn max_density(&self) -> f64 {
+        1.
+    }
+}
+
+fn default_octaves() -> u32 {
+    5
+}
+
+fn default_fade() -> bool {
+    true
+}
@@ -0,0 +1,279 @@
+//! Distributed rendering over TCP.
+//!
+//! A [`Coordinator`] splits the image into tiles, sends the camera and world to every worker once,
+//! then hands out tiles until the image is complete. Workers are plain processes running
+//! [`serve`]. Messages are newline-delimited JSON.
+//!
+//! If a worker disconnects, times out or replies with garbage, its in-flight tile is put back in
+//! the queue and picked up by one of the remaining workers. A worker that stays connected but stops
+//! answering times out after [`Coordinator::timeout`]. Since every pixel has its own seeded
+//! random stream, the assembled image is identical to a local `Camera::render`.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use indicatif::ProgressBar;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{Camera, Tile};
+use crate::hit_record::Hittable;
+use crate::raw_image_buffer::RawImageBuffer;
+use crate::Color;
+
+#[derive(Serialize, Deserialize)]
+enum Request<T> {
+    Scene { camera: Box<Camera>, world: T },
+    Render(Tile),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Rendered {
+    tile: Tile,
+    pixels: Vec<Color>,
+}
+
+fn send<M: Serialize>(writer: &mut impl Write, message: &M) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn receive<M: DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<Option<M>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+/// Accept coordinator connections forever, rendering the tiles they ask for.
+///
+/// `T` must match the world type the coordinator sends, e.g. `Vec<Shape>`.
+pub fn serve<T>(listener: TcpListener) -> io::Result<()>
+where
+    T: Hittable + DeserializeOwned + Send + Sync + 'static,
+{
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(err) = handle_connection::<T>(stream) {
+                eprintln!("Connection closed: {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Serve a single coordinator until it disconnects.
+pub fn handle_connection<T>(stream: TcpStream) -> io::Result<()>
+where
+    T: Hittable + DeserializeOwned + Sync,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut scene: Option<(Camera, T)> = None;
+
+    while let Some(request) = receive(&mut reader)? {
+        match request {
+            Request::Scene { camera, world } => scene = Some((*camera, world)),
+            Request::Render(tile) => {
+                let (camera, world) = scene.as_ref().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "tile requested before scene")
+                })?;
+                let pixels = camera.render_tile(world, &tile);
+                send(&mut writer, &Rendered { tile, pixels })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Tiles waiting to be rendered, shared between the worker connections.
+struct Schedule {
+    state: Mutex<ScheduleState>,
+    changed: Condvar,
+}
+
+struct ScheduleState {
+    pending: VecDeque<Tile>,
+    remaining: usize,
+}
+
+impl Schedule {
+    fn new(tiles: Vec<Tile>) -> Self {
+        Self {
+            state: Mutex::new(ScheduleState {
+                remaining: tiles.len(),
+                pending: tiles.into(),
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Wait for the next tile. Returns `None` once every tile has been rendered.
+    fn next(&self) -> Option<Tile> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(tile) = state.pending.pop_front() {
+                return Some(tile);
+            }
+            if state.remaining == 0 {
+                return None;
+            }
+            // Tiles are still in flight on other workers and may come back if those fail
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    fn complete(&self) {
+        self.state.lock().unwrap().remaining -= 1;
+        self.changed.notify_all();
+    }
+
+    /// Drop a failed worker, re-queueing the tile it was working on.
+    fn fail(&self, tile: Option<Tile>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(tile) = tile {
+            state.pending.push_back(tile);
+        }
+        self.changed.notify_all();
+    }
+
+    fn remaining(&self) -> usize {
+        self.state.lock().unwrap().remaining
+    }
+}
+
+pub struct Coordinator {
+    workers: Vec<SocketAddr>,
+    tile_size: u32,
+    timeout: Duration,
+}
+
+impl Coordinator {
+    pub fn new(workers: Vec<SocketAddr>) -> Self {
+        Self {
+            workers,
+            tile_size: 32,
+            timeout: Duration::from_secs(300),
+        }
+    }
+
+    /// Side length of the square tiles sent to workers
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// How long to wait on a worker before treating it as failed. It has to cover rendering a
+    /// whole tile, so the default of five minutes is generous.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Render the image on the workers. Fails only if every worker fails before the image is done.
+    pub fn render<T>(&self, camera: &Camera, world: &T) -> io::Result<RawImageBuffer>
+    where
+        T: Serialize + Sync,
+    {
+        let tiles = camera.tiles(self.tile_size);
+        let pb = ProgressBar::new(tiles.len() as u64);
+        let schedule = Schedule::new(tiles);
+        let colors = Mutex::new(vec![
+            Color::ZERO;
+            (camera.image_width * camera.image_height) as usize
+        ]);
+
+        thread::scope(|scope| {
+            for &addr in &self.workers {
+                let (schedule, colors, pb) = (&schedule, &colors, &pb);
+                scope.spawn(move || {
+                    let mut in_flight = None;
+                    let result =
+                        self.dispatch(addr, camera, world, schedule, colors, pb, &mut in_flight);
+                    if let Err(err) = result {
+                        pb.println(format!("Worker {addr} failed: {err}"));
+                        schedule.fail(in_flight);
+                    }
+                });
+            }
+        });
+        pb.finish();
+
+        let remaining = schedule.remaining();
+        if remaining > 0 {
+            return Err(io::Error::other(format!(
+                "all workers failed with {remaining} tiles left to render"
+            )));
+        }
+
+        Ok(RawImageBuffer::from_colors(
+            camera.image_width,
+            camera.image_height,
+            &colors.into_inner().unwrap(),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn dispatch<T>(
+        &self,
+        addr: SocketAddr,
+        camera: &Camera,
+        world: &T,
+        schedule: &Schedule,
+        colors: &Mutex<Vec<Color>>,
+        pb: &ProgressBar,
+        in_flight: &mut Option<Tile>,
+    ) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        let stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+
+        send(
+            &mut writer,
+            &Request::Scene {
+                camera: Box::new(camera.clone()),
+                world,
+            },
+        )?;
+
+        while let Some(tile) = schedule.next() {
+            *in_flight = Some(tile);
+            send(&mut writer, &Request::<&T>::Render(tile))?;
+
+            let rendered: Rendered = receive(&mut reader)?
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            if rendered.tile != tile || rendered.pixels.len() != tile.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "worker returned the wrong tile",
+                ));
+            }
+
+            let mut colors = colors.lock().unwrap();
+            for (row, pixels) in rendered.pixels.chunks(tile.width as usize).enumerate() {
+                let start = ((tile.y + row as u32) * camera.image_width + tile.x) as usize;
+                colors[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+            drop(colors);
+
+            *in_flight = None;
+            schedule.complete();
+            pb.inc(1);
+        }
+
+        Ok(())
+    }
+}
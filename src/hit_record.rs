@@ -1,44 +1,88 @@
 use std::ops::Range;
+use std::sync::OnceLock;
 
-use crate::material::Material;
+use crate::material::{Lambertian, Material};
 use crate::ray::Ray;
-use crate::Vec3;
+use crate::shapes::Shape;
+use crate::texture::TextureCoords;
+use crate::{Vec2, Vec3};
 
-#[derive(Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum FaceSide {
     #[default]
     Front,
     Back,
 }
 
-pub struct HitRecord {
+/// Where a ray hit the world. The material is borrowed from the shape that was hit, so
+/// intersection tests don't allocate or touch reference counts.
+#[derive(Clone)]
+pub struct HitRecord<'a> {
     pub point: Vec3,
+    /// Shading normal, facing the incoming ray. This is the geometric normal unless a normal or
+    /// bump map has perturbed it.
     pub normal: Vec3,
+    /// Normal of the surface itself, facing the incoming ray. Never perturbed, so it tells which
+    /// side of the surface a direction is really on.
+    pub geometric_normal: Vec3,
     pub t: f64,
+    /// Surface texture coordinates
+    pub uv: Vec2,
+    /// How the point moves as `u` and `v` change. Zero if the shape has no texture coordinates.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Unit vectors completing an orthonormal frame with `normal`, `tangent` following `u`. Normal
+    /// maps are given in this frame.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    /// Width of the area seen through the ray at the hit point, for texture filtering
+    pub footprint: f64,
+    /// Index of refraction on the outside of the surface. Set by the camera from the media the ray
+    /// is in.
+    pub exterior_ior: f64,
     pub face_side: FaceSide,
-    pub material: Material,
+    pub material: &'a dyn Material,
 }
 
-impl Default for HitRecord {
+impl Default for HitRecord<'_> {
     fn default() -> Self {
+        static DEFAULT_MATERIAL: OnceLock<Lambertian> = OnceLock::new();
         Self {
             point: Vec3::ZERO,
             normal: Vec3::ZERO,
+            geometric_normal: Vec3::ZERO,
             t: 0.,
+            uv: Vec2::ZERO,
+            dpdu: Vec3::ZERO,
+            dpdv: Vec3::ZERO,
+            tangent: Vec3::ZERO,
+            bitangent: Vec3::ZERO,
+            footprint: 0.,
+            exterior_ior: 1.,
             face_side: FaceSide::default(),
-            material: Material::Lambertian { albedo: Vec3::ONE },
+            material: DEFAULT_MATERIAL.get_or_init(|| Lambertian::new(Vec3::ONE)),
         }
     }
 }
 
-impl HitRecord {
-    pub fn new(point: Vec3, normal: Vec3, t: f64, face_side: FaceSide, material: Material) -> Self {
+impl<'a> HitRecord<'a> {
+    pub fn new(
+        point: Vec3,
+        normal: Vec3,
+        t: f64,
+        uv: Vec2,
+        face_side: FaceSide,
+        material: &'a dyn Material,
+    ) -> Self {
         Self {
             point,
             normal,
+            geometric_normal: normal,
             t,
+            uv,
             face_side,
             material,
+            ..Default::default()
         }
     }
 
@@ -54,18 +98,102 @@ impl HitRecord {
             FaceSide::Front => outward_normal,
             FaceSide::Back => -outward_normal,
         };
+        self.geometric_normal = self.normal;
+    }
+
+    /// Build the tangent frame from `dpdu`, `dpdv` and the normal. Call after the normal is set.
+    pub fn set_tangents(&mut self) {
+        let tangent = self.dpdu - self.normal * self.normal.dot(self.dpdu);
+        self.tangent = if tangent.length_squared() > 1e-16 {
+            tangent.normalize()
+        } else {
+            // No texture coordinates here, e.g. at a sphere's pole. Any tangent will do.
+            self.normal.any_orthonormal_vector()
+        };
+        self.bitangent = self.normal.cross(self.tangent);
+        // Keep the bitangent following `v`, so mirrored texture coordinates don't flip normal maps
+        if self.bitangent.dot(self.dpdv) < 0. {
+            self.bitangent = -self.bitangent;
+        }
+    }
+
+    /// Replace the shading normal, keeping the tangent frame orthonormal around it.
+    pub fn set_shading_normal(&mut self, normal: Vec3) {
+        let tangent = self.tangent - normal * normal.dot(self.tangent);
+        self.tangent = if tangent.length_squared() > 1e-16 {
+            tangent.normalize()
+        } else {
+            normal.any_orthonormal_vector()
+        };
+        let handedness = self.normal.cross(self.tangent).dot(self.bitangent).signum();
+        self.normal = normal;
+        self.bitangent = handedness * normal.cross(self.tangent);
+    }
+
+    /// Whether `direction` is on the same side of the surface as of the shading normal. A tilted
+    /// shading normal can put directions that are above it below the surface, and following them
+    /// would leak light through the surface.
+    pub fn is_consistent(&self, direction: Vec3) -> bool {
+        direction.dot(self.geometric_normal) * direction.dot(self.normal) > 0.
+    }
+
+    /// Express a world space direction in the shading frame, with the normal along Z.
+    pub fn to_local(&self, direction: Vec3) -> Vec3 {
+        Vec3::new(
+            direction.dot(self.tangent),
+            direction.dot(self.bitangent),
+            direction.dot(self.normal),
+        )
+    }
+
+    /// The inverse of [`HitRecord::to_local`].
+    pub fn from_local(&self, direction: Vec3) -> Vec3 {
+        direction.x * self.tangent + direction.y * self.bitangent + direction.z * self.normal
+    }
+
+    /// Set the footprint from the ray's cone. Call after the normal is set.
+    pub fn set_footprint(&mut self, ray: &Ray) {
+        // Seen at an angle the cone covers an ellipse. Use the geometric mean of its axes, which
+        // blurs less towards the horizon than the long axis would, at the cost of some aliasing.
+        let cos_theta = ray.direction.normalize().dot(self.normal).abs().max(1e-3);
+        self.footprint = ray.footprint(self.t) / cos_theta.sqrt();
+    }
+
+    pub fn texture_coords(&self) -> TextureCoords {
+        let uv_length = |dpd: Vec3| {
+            let length = dpd.length();
+            if length > 0. {
+                self.footprint / length
+            } else {
+                0.
+            }
+        };
+
+        TextureCoords {
+            uv: self.uv,
+            point: self.point,
+            normal: self.normal,
+            footprint: self.footprint,
+            uv_footprint: Vec2::new(uv_length(self.dpdu), uv_length(self.dpdv)),
+        }
     }
 }
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>>;
+
+    /// Shapes with emissive materials, which the camera samples directly as lights. Worlds that
+    /// don't list any are only lit by rays finding their lights by chance.
+    fn lights(&self) -> Vec<&Shape> {
+        Vec::new()
+    }
 }
 
 impl<T> Hittable for Vec<T>
 where
     T: Hittable + Sync,
 {
-    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
         let mut closest_so_far = interval.end;
         let mut hit_record = None;
 
@@ -78,4 +206,8 @@ where
 
         hit_record
     }
+
+    fn lights(&self) -> Vec<&Shape> {
+        self.iter().flat_map(|hittable| hittable.lights()).collect()
+    }
 }
@@ -1,9 +1,20 @@
+pub mod background;
 pub mod camera;
+pub mod density;
+#[cfg(feature = "serde")]
+pub mod distributed;
 pub mod hit_record;
+pub mod light;
 pub mod material;
+pub mod medium;
 pub mod raw_image_buffer;
 pub mod ray;
+#[cfg(feature = "serde")]
+pub mod scene;
 pub mod shapes;
+pub mod sky;
+pub mod spectrum;
+pub mod texture;
 pub mod utils;
 pub mod vectors;
 
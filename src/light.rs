@@ -0,0 +1,334 @@
+//! Sampling lights directly.
+//!
+//! Rays bouncing at random rarely find small lights, so at every opaque surface the camera also
+//! picks a direction towards one of the lights and traces a shadow ray to see whether it's lit
+//! (next-event estimation). Light can then be found both ways, so each is weighed with multiple
+//! importance sampling: light sampling is trusted for small lights and rough surfaces, scattering
+//! for large lights and glossy reflections.
+//!
+//! Besides emissive shapes, there are lights without geometry ([`Light`]): points, spots and the
+//! sun. Points and spots, and suns without a disc, can only be found by light sampling.
+
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::shapes::Shape;
+use crate::vectors::{cone_pdf, random_in_cone};
+use crate::{Color, Vec3};
+
+/// A light without geometry.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Light {
+    Point(PointLight),
+    Spot(SpotLight),
+    Sun(SunLight),
+}
+
+/// Light shining equally in every direction from a point, falling off with the square of the
+/// distance.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct PointLight {
+    pub position: Vec3,
+    pub color: Color,
+    /// Scales `color`, as power per unit solid angle
+    #[cfg_attr(feature = "serde", serde(default = "one"))]
+    pub intensity: f64,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, color: Color) -> Self {
+        Self {
+            position,
+            color,
+            intensity: 1.,
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+/// A point light shining in a cone around `direction`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub color: Color,
+    #[cfg_attr(feature = "serde", serde(default = "one"))]
+    pub intensity: f64,
+    /// Angle between the axis and the edge of the cone, in degrees
+    pub angle: f64,
+    /// Angle inside the edge over which the light fades out smoothly, in degrees. 0 gives a hard
+    /// edge.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub falloff: f64,
+}
+
+impl SpotLight {
+    pub fn new(position: Vec3, direction: Vec3, color: Color, angle: f64) -> Self {
+        Self {
+            position,
+            direction,
+            color,
+            intensity: 1.,
+            angle,
+            falloff: 0.,
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn falloff(mut self, falloff: f64) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Fraction of the intensity shone towards the unit `direction`.
+    fn cone(&self, direction: Vec3) -> f64 {
+        let cos_theta = self.direction.normalize().dot(direction);
+        let cos_outer = self.angle.to_radians().cos();
+        let cos_inner = (self.angle - self.falloff).max(0.).to_radians().cos();
+        if cos_theta >= cos_inner {
+            1.
+        } else if cos_theta <= cos_outer {
+            0.
+        } else {
+            let t = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3. - 2. * t)
+        }
+    }
+}
+
+/// Light from a distant disc in the sky, like the sun. Shadows are softer the larger the disc.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SunLight {
+    /// Towards the sun
+    pub direction: Vec3,
+    pub color: Color,
+    /// Scales `color`, as light falling on a surface facing the sun
+    #[cfg_attr(feature = "serde", serde(default = "one"))]
+    pub intensity: f64,
+    /// Angle the disc covers, in degrees. 0 gives perfectly sharp shadows, but then the sun can't
+    /// be seen or reflected.
+    #[cfg_attr(feature = "serde", serde(default = "default_angular_diameter"))]
+    pub angular_diameter: f64,
+}
+
+impl SunLight {
+    pub fn new(direction: Vec3, color: Color) -> Self {
+        Self {
+            direction,
+            color,
+            intensity: 1.,
+            angular_diameter: default_angular_diameter(),
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn angular_diameter(mut self, angular_diameter: f64) -> Self {
+        self.angular_diameter = angular_diameter;
+        self
+    }
+
+    /// Cosine of the angle between the center and the edge of the disc, or `None` for a point.
+    fn cos_radius(&self) -> Option<f64> {
+        (self.angular_diameter > 0.).then(|| (0.5 * self.angular_diameter).to_radians().cos())
+    }
+
+    /// Light seen looking along the unit `direction`.
+    fn radiance(&self, direction: Vec3) -> Color {
+        match self.cos_radius() {
+            Some(cos_radius) if self.direction.normalize().dot(direction) >= cos_radius => {
+                // Spread over the disc, so a surface facing it gets `intensity`
+                self.color * self.intensity * cone_pdf(cos_radius)
+            }
+            _ => Color::ZERO,
+        }
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        match self.cos_radius() {
+            Some(cos_radius) if self.direction.normalize().dot(direction) >= cos_radius => {
+                cone_pdf(cos_radius)
+            }
+            _ => 0.,
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+impl From<SunLight> for Light {
+    fn from(light: SunLight) -> Self {
+        Light::Sun(light)
+    }
+}
+
+/// A direction picked by [`Lights::sample`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightSample {
+    /// Towards an emissive shape or the disc of the sun. How much light comes from there is found
+    /// by tracing a shadow ray, and weighed by [`Lights::pdf`].
+    Direction(Vec3),
+    /// Towards a light that only light sampling can find, whose `radiance` reaches the origin
+    /// unless something within `distance` is in the way. The radiance is already divided by the
+    /// chance of picking the light.
+    Delta {
+        direction: Vec3,
+        distance: f64,
+        radiance: Color,
+    },
+}
+
+/// The lights in a world, sampled with equal probability.
+#[derive(Clone, Debug, Default)]
+pub struct Lights<'a> {
+    shapes: Vec<&'a Shape>,
+    lights: &'a [Light],
+}
+
+impl<'a> Lights<'a> {
+    pub fn new(shapes: Vec<&'a Shape>, lights: &'a [Light]) -> Self {
+        Self { shapes, lights }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len() + self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sample a direction from `origin` towards a light picked at random.
+    pub fn sample(&self, origin: Vec3, rng: &mut dyn RngCore) -> Option<LightSample> {
+        if self.is_empty() {
+            return None;
+        }
+        let i = rng.gen_range(0..self.len());
+        if i < self.shapes.len() {
+            let direction = self.shapes[i].sample_direction(origin, rng)?;
+            return Some(LightSample::Direction(direction));
+        }
+
+        let count = self.len() as f64;
+        let sample = match &self.lights[i - self.shapes.len()] {
+            Light::Point(point) => {
+                let to_light = point.position - origin;
+                let distance = to_light.length();
+                LightSample::Delta {
+                    direction: to_light / distance,
+                    distance,
+                    radiance: count * point.color * point.intensity / (distance * distance),
+                }
+            }
+            Light::Spot(spot) => {
+                let to_light = spot.position - origin;
+                let distance = to_light.length();
+                let direction = to_light / distance;
+                let cone = spot.cone(-direction);
+                LightSample::Delta {
+                    direction,
+                    distance,
+                    radiance: count * cone * spot.color * spot.intensity / (distance * distance),
+                }
+            }
+            Light::Sun(sun) => match sun.cos_radius() {
+                Some(cos_radius) => LightSample::Direction(random_in_cone(
+                    sun.direction.normalize(),
+                    cos_radius,
+                    rng,
+                )),
+                None => LightSample::Delta {
+                    direction: sun.direction.normalize(),
+                    distance: f64::INFINITY,
+                    radiance: count * sun.color * sun.intensity,
+                },
+            },
+        };
+        Some(sample)
+    }
+
+    /// Probability density of `sample` choosing `direction` from `origin`, per unit solid angle.
+    /// Lights that only light sampling can find are left out.
+    pub fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let unit_direction = direction.normalize();
+        let shapes: f64 = self
+            .shapes
+            .iter()
+            .map(|light| light.pdf(origin, direction))
+            .sum();
+        let suns: f64 = self
+            .lights
+            .iter()
+            .map(|light| match light {
+                Light::Sun(sun) => sun.pdf(unit_direction),
+                Light::Point(_) | Light::Spot(_) => 0.,
+            })
+            .sum();
+        (shapes + suns) / self.len() as f64
+    }
+
+    /// Light from the discs of suns, seen by rays leaving the world along `direction`.
+    pub fn sun_radiance(&self, direction: Vec3) -> Color {
+        let direction = direction.normalize();
+        self.lights
+            .iter()
+            .map(|light| match light {
+                Light::Sun(sun) => sun.radiance(direction),
+                Light::Point(_) | Light::Spot(_) => Color::ZERO,
+            })
+            .sum()
+    }
+}
+
+/// Weight of a sample taken with density `pdf`, when the same light could also have been found
+/// with density `other`. The weights of both add up to one.
+pub fn power_heuristic(pdf: f64, other: f64) -> f64 {
+    let (pdf, other) = (pdf * pdf, other * other);
+    if pdf + other > 0. {
+        pdf / (pdf + other)
+    } else {
+        0.
+    }
+}
+
+#[cfg(feature = "serde")]
+fn one() -> f64 {
+    1.
+}
+
+fn default_angular_diameter() -> f64 {
+    0.53
+}
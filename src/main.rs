@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, ValueEnum};
+use ray_tow::camera::CameraBuilder;
+use ray_tow::raw_image_buffer::RawImageBuffer;
+use ray_tow::scene::Scene;
+use ray_tow::shapes::Shape;
+use ray_tow::Color;
+
+/// Render a scene file to an image.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Scene file to render
+    scene: PathBuf,
+
+    /// Where to write the rendered image
+    #[arg(short, long, default_value = "output/latest.png")]
+    output: PathBuf,
+
+    /// Image format, guessed from the output extension if not given
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Image resolution as WIDTHxHEIGHT, or just WIDTH to keep the scene's aspect ratio
+    #[arg(short, long, value_parser = parse_resolution)]
+    resolution: Option<(u32, Option<u32>)>,
+
+    /// Samples per pixel
+    #[arg(short, long)]
+    spp: Option<u32>,
+
+    /// Maximum number of bounces per ray
+    #[arg(short = 'd', long)]
+    max_depth: Option<u32>,
+
+    /// Seed for the random number generators
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Trace wavelengths rather than RGB, to show dispersion
+    #[arg(long)]
+    spectral: bool,
+
+    /// Number of render threads, defaults to one per core
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Re-render whenever the scene file changes, refining the image in passes until the full
+    /// sample count is reached
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Samples per pixel in each watch mode pass
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pass_spp: u32,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Png,
+    Jpeg,
+    Bmp,
+    Tiff,
+    Ppm,
+    Tga,
+}
+
+impl From<Format> for image::ImageFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Png => image::ImageFormat::Png,
+            Format::Jpeg => image::ImageFormat::Jpeg,
+            Format::Bmp => image::ImageFormat::Bmp,
+            Format::Tiff => image::ImageFormat::Tiff,
+            Format::Ppm => image::ImageFormat::Pnm,
+            Format::Tga => image::ImageFormat::Tga,
+        }
+    }
+}
+
+fn parse_resolution(s: &str) -> Result<(u32, Option<u32>), String> {
+    let parse = |n: &str| {
+        n.parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("invalid resolution `{s}`, expected e.g. 1200x800"))
+    };
+    match s.split_once('x') {
+        Some((width, height)) => Ok((parse(width)?, Some(parse(height)?))),
+        None => Ok((parse(s)?, None)),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    if args.watch {
+        return watch(&args);
+    }
+
+    let scene = match Scene::load(&args.scene) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("Failed to load {}: {err}", args.scene.display());
+            std::process::exit(1);
+        }
+    };
+
+    let camera = configure(&args, scene.camera).build();
+
+    let start = Instant::now();
+    let render_buffer = camera.render(&scene.world);
+    let elapsed = start.elapsed();
+
+    save(&render_buffer, &args.output, args.format)?;
+
+    let pixels = camera.image_width as u64 * camera.image_height as u64;
+    let samples = pixels * camera.samples_per_pixel as u64;
+    println!(
+        "Rendered {} objects at {}x{}, {} spp, max depth {}",
+        scene.world.len(),
+        camera.image_width,
+        camera.image_height,
+        camera.samples_per_pixel,
+        camera.max_depth
+    );
+    println!(
+        "Took {:.2?} on {} threads: {:.2} Msamples/s, {:.2?} per pixel",
+        elapsed,
+        rayon::current_num_threads(),
+        samples as f64 / elapsed.as_secs_f64() / 1e6,
+        elapsed.div_f64(pixels.max(1) as f64)
+    );
+    println!("Saved to {}", args.output.display());
+
+    Ok(())
+}
+
+/// Apply the command-line overrides to the scene's camera.
+fn configure(args: &Args, mut camera: CameraBuilder) -> CameraBuilder {
+    if let Some((width, height)) = args.resolution {
+        camera = camera.image_width(width);
+        if let Some(height) = height {
+            camera = camera.image_height(height);
+        }
+    }
+    if let Some(spp) = args.spp {
+        camera = camera.samples_per_pixel(spp);
+    }
+    if let Some(max_depth) = args.max_depth {
+        camera = camera.max_depth(max_depth);
+    }
+    if let Some(seed) = args.seed {
+        camera = camera.seed(seed);
+    }
+    if args.spectral {
+        camera = camera.spectral(true);
+    }
+    camera
+}
+
+fn save(
+    render_buffer: &RawImageBuffer,
+    output: &Path,
+    format: Option<Format>,
+) -> image::ImageResult<()> {
+    match format {
+        Some(format) => render_buffer.save_with_format(output, format.into()),
+        None => render_buffer.save(output),
+    }
+}
+
+/// Poll the scene file, restarting the progressive render whenever it changes.
+fn watch(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut render: Option<thread::JoinHandle<()>> = None;
+    let mut last_modified = None;
+
+    println!("Watching {} for changes", args.scene.display());
+    loop {
+        let modified = std::fs::metadata(&args.scene)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        // A missing file is usually an editor halfway through saving, so wait for it to reappear
+        if modified.is_some() && modified != last_modified {
+            last_modified = modified;
+
+            // Stop the in-flight render before starting over
+            cancel.store(true, Ordering::Relaxed);
+            if let Some(render) = render.take() {
+                render.join().map_err(|_| "render thread panicked")?;
+            }
+            cancel.store(false, Ordering::Relaxed);
+
+            match Scene::load(&args.scene) {
+                Ok(scene) => {
+                    let camera = configure(args, scene.camera);
+                    let (output, format) = (args.output.clone(), args.format);
+                    let (pass_spp, cancel) = (args.pass_spp, cancel.clone());
+                    render = Some(thread::spawn(move || {
+                        if let Err(err) =
+                            refine(camera, &scene.world, pass_spp, &cancel, &output, format)
+                        {
+                            eprintln!("Failed to save {}: {err}", output.display());
+                        }
+                    }));
+                }
+                Err(err) => eprintln!("Failed to load {}: {err}", args.scene.display()),
+            }
+        }
+
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// Render passes of `pass_spp` samples, saving the running average after each one, until the
+/// camera's sample count is reached or the render is cancelled. The last pass renders whatever is
+/// left, so the total is exactly the camera's sample count.
+fn refine(
+    camera: CameraBuilder,
+    world: &Vec<Shape>,
+    pass_spp: u32,
+    cancel: &AtomicBool,
+    output: &Path,
+    format: Option<Format>,
+) -> image::ImageResult<()> {
+    let target_spp = camera.clone().build().samples_per_pixel;
+    let pass_spp = pass_spp.min(target_spp).max(1);
+    let passes = target_spp.div_ceil(pass_spp);
+    let last = camera
+        .clone()
+        .samples_per_pixel(target_spp - (passes - 1) * pass_spp)
+        .build();
+    let camera = camera.samples_per_pixel(pass_spp).build();
+
+    let start = Instant::now();
+    let mut sum = vec![Color::ZERO; (camera.image_width * camera.image_height) as usize];
+    let mut samples = 0;
+    for pass in 0..passes {
+        let camera = if pass + 1 == passes { &last } else { &camera };
+        let Some(colors) = camera.render_pass(world, pass, cancel) else {
+            return Ok(());
+        };
+        let spp = camera.samples_per_pixel;
+        sum.iter_mut()
+            .zip(colors)
+            .for_each(|(sum, color)| *sum += color * spp as f64);
+        samples += spp;
+
+        let average: Vec<Color> = sum.iter().map(|color| *color / samples as f64).collect();
+        let render_buffer =
+            RawImageBuffer::from_colors(camera.image_width, camera.image_height, &average);
+        save(&render_buffer, output, format)?;
+
+        println!(
+            "Pass {}/{passes}: {samples} spp after {:.2?}",
+            pass + 1,
+            start.elapsed()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::Material;
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::{Color, Vec2, Vec3};
+
+/// Wraps another material, perturbing its shading normal with a tangent-space normal map.
+///
+/// The map's red, green and blue channels hold the normal along the tangent, bitangent and normal,
+/// remapped from `-1..1` to `0..1`, as most tools export them. Load image maps with
+/// `ImageTexture::load_linear` (`srgb = false` in scene files).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct NormalMap {
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub map: Arc<dyn Texture>,
+    /// Scales the tilt of the normals, 0 to ignore the map
+    #[cfg_attr(feature = "serde", serde(default = "default_strength"))]
+    pub strength: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl NormalMap {
+    pub fn new(map: Arc<dyn Texture>, material: Arc<dyn Material>) -> Self {
+        Self {
+            map,
+            strength: default_strength(),
+            material,
+        }
+    }
+
+    pub fn strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    fn shade<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let encoded = self.map.value(&hit_record.texture_coords());
+        let local = 2. * encoded - 1.;
+        let normal = (self.strength * local.x * hit_record.tangent
+            + self.strength * local.y * hit_record.bitangent
+            + local.z * hit_record.normal)
+            .normalize_or_zero();
+
+        let mut shaded = hit_record.clone();
+        // Normals pointing into the surface are bad data, keep the surface's own
+        if normal.dot(hit_record.normal) > 0. {
+            shaded.set_shading_normal(normal);
+        }
+        shaded
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "normal_map"))]
+impl Material for NormalMap {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let shaded = self.shade(hit_record);
+        self.material
+            .scatter(ray, &shaded, rng)
+            .filter(|(scattered, _)| shaded.is_consistent(scattered.direction))
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.material.emitted(ray, &self.shade(hit_record))
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let shaded = self.shade(hit_record);
+        if !shaded.is_consistent(scattered.direction) {
+            return Color::ZERO;
+        }
+        self.material.eval(ray, &shaded, scattered)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.material.pdf(ray, &self.shade(hit_record), scattered)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.material.medium()
+    }
+}
+
+/// Wraps another material, perturbing its shading normal as if the surface were displaced along
+/// its normal by a height texture. Fine detail like grain or brushing doesn't need an authored
+/// normal map this way: any scalar texture, including procedural noise, can drive it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct BumpMap {
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub height: Arc<dyn Texture>,
+    /// World space displacement for a height of 1
+    pub scale: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl BumpMap {
+    pub fn new(height: Arc<dyn Texture>, scale: f64, material: Arc<dyn Material>) -> Self {
+        Self {
+            height,
+            scale,
+            material,
+        }
+    }
+
+    fn shade<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let coords = hit_record.texture_coords();
+        let height = self.height.scalar(&coords);
+
+        // Finite differences over about half the pixel footprint, so the bumps are filtered like
+        // the rest of the texture. The point moves too, for solid textures.
+        let slope = |dpd: Vec3, direction: Vec2| {
+            let length = dpd.length();
+            if length == 0. {
+                return 0.;
+            }
+            let step = (0.5 * hit_record.footprint).max(1e-4) / length;
+            let mut shifted = coords;
+            shifted.uv += step * direction;
+            shifted.point += step * dpd;
+            (self.height.scalar(&shifted) - height) / step
+        };
+
+        let normal = hit_record.normal;
+        let dpdu = hit_record.dpdu + self.scale * slope(hit_record.dpdu, Vec2::X) * normal;
+        let dpdv = hit_record.dpdv + self.scale * slope(hit_record.dpdv, Vec2::Y) * normal;
+        let bumped = dpdu.cross(dpdv).normalize_or_zero();
+
+        let mut shaded = hit_record.clone();
+        if bumped != Vec3::ZERO {
+            // The cross product follows the texture's handedness, not the side we're on
+            shaded.set_shading_normal(if bumped.dot(normal) < 0. {
+                -bumped
+            } else {
+                bumped
+            });
+        }
+        shaded
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "bump_map"))]
+impl Material for BumpMap {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let shaded = self.shade(hit_record);
+        self.material
+            .scatter(ray, &shaded, rng)
+            .filter(|(scattered, _)| shaded.is_consistent(scattered.direction))
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.material.emitted(ray, &self.shade(hit_record))
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let shaded = self.shade(hit_record);
+        if !shaded.is_consistent(scattered.direction) {
+            return Color::ZERO;
+        }
+        self.material.eval(ray, &shaded, scattered)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.material.pdf(ray, &self.shade(hit_record), scattered)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.material.medium()
+    }
+}
+
+fn default_strength() -> f64 {
+    1.
+}
@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::microfacet::{fresnel_dielectric, reflect, Ggx};
+use crate::material::Material;
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::vectors::refract;
+use crate::{Color, Vec3};
+
+/// How many times light can bounce between the base and the underside of the coat before it's
+/// given up as absorbed
+const MAX_BOUNCES: u32 = 16;
+
+/// A clear dielectric coat over any other material, like lacquered car paint, varnished wood or
+/// glazed ceramic.
+///
+/// Light either reflects off the coat, as often as the Fresnel equations say, or refracts into it
+/// and bounces between the base and the underside of the coat until it gets back out. The coat
+/// is infinitely thin, so light leaves where it entered, but it can absorb light in proportion to
+/// `thickness`, tinting the base more at grazing angles. The bounces inside only have a closed form
+/// over a diffuse base, so over any other `eval` and `pdf` are zero: lights are then only found by
+/// scattering, as for a mirror.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Coated {
+    pub base: Arc<dyn Material>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_ior", deserialize_with = "super::positive")
+    )]
+    pub ior: f64,
+    /// Perceptual roughness of the coat, 0 for a mirror finish to 1 for fully rough
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::texture::serde_texture", default = "zero")
+    )]
+    pub roughness: Arc<dyn Texture>,
+    #[cfg_attr(feature = "serde", serde(default = "default_thickness"))]
+    pub thickness: f64,
+    /// Fraction of light absorbed per unit of thickness travelled, per channel
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub absorption: Color,
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, ior: f64) -> Self {
+        Self {
+            base,
+            ior,
+            roughness: zero(),
+            thickness: default_thickness(),
+            absorption: Color::ZERO,
+        }
+    }
+
+    pub fn roughness(mut self, roughness: Arc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn absorption(mut self, absorption: Color, thickness: f64) -> Self {
+        self.absorption = absorption;
+        self.thickness = thickness;
+        self
+    }
+
+    /// Fraction of light left after crossing the coat at `cos` to the normal.
+    fn transmittance(&self, cos: f64) -> Color {
+        (-self.absorption * self.thickness / cos.max(1e-6)).exp()
+    }
+
+    /// Fraction of light scattered diffusely up from the base that the underside of the coat
+    /// reflects back down, and what is left of that after absorption on the way up and back.
+    fn internal_reflectance(&self, eta: f64) -> (f64, Color) {
+        // Cosine-weighted average over the hemisphere, by the midpoint rule in cos²
+        const STEPS: u32 = 32;
+        let mut reflectance = 0.;
+        let mut returned = Color::ZERO;
+        for i in 0..STEPS {
+            let cos = ((i as f64 + 0.5) / STEPS as f64).sqrt();
+            let fresnel = fresnel_dielectric(cos, 1. / eta);
+            reflectance += fresnel;
+            returned += fresnel * self.transmittance(cos) * self.transmittance(cos);
+        }
+        (reflectance / STEPS as f64, returned / STEPS as f64)
+    }
+
+    /// The BSDF times the cosine term for light scattered into `scattered`, and the density of
+    /// `scatter` choosing it. Both are zero over bases that aren't diffuse.
+    fn eval_pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> (Color, f64) {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        if wo.z <= 0. || wi.z <= 0. || !self.base.is_diffuse() {
+            return (Color::ZERO, 0.);
+        }
+
+        // Reflection off the coat
+        let eta = self.ior / hit_record.exterior_ior;
+        let roughness = self.roughness.scalar(&hit_record.texture_coords());
+        let ggx = Ggx::from_roughness(roughness.clamp(0., 1.));
+        let m = (wo + wi).normalize();
+        let fresnel = fresnel_dielectric(wo.dot(m), eta);
+        let mut value = Color::splat(fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4. * wo.z));
+        let mut pdf = fresnel * ggx.visible_pdf(wo, m) / (4. * wo.dot(m));
+
+        // Light that isn't reflected refracts into the coat at the macroscopic normal, as in
+        // `scatter`, and reaches the base
+        let normal = hit_record.normal;
+        let under_coat = refracted_fraction(&ggx, wo, eta);
+        let mut below = hit_record.clone();
+        below.exterior_ior = self.ior;
+        let down = refract(ray.direction.normalize(), normal, 1. / eta);
+        let mut inside = Ray::new(hit_record.point, down);
+        inside.wavelengths = ray.wavelengths;
+        let entering = under_coat * self.transmittance(-down.dot(normal));
+
+        // Traced back, light leaving along `scattered` was heading `up` under the coat. The
+        // change of solid angle across the coat is the same for the BSDF and the pdf.
+        let up = -refract(-scattered.direction.normalize(), normal, 1. / eta);
+        let cos_up = up.dot(normal);
+        let leaving = 1. - fresnel_dielectric(cos_up, 1. / eta);
+        let solid_angle = wi.z / (eta * eta * cos_up);
+        let towards_coat = Ray::new(hit_record.point, up);
+        let base_value = self.base.eval(&inside, &below, &towards_coat);
+        let base_pdf = self.base.pdf(&inside, &below, &towards_coat);
+
+        // Light reflected back down by the coat bounces off the base again, and again. A diffuse
+        // base scatters each bounce the same way, so the bounces sum as a geometric series.
+        let (reflectance, returned) = self.internal_reflectance(eta);
+        let albedo = if base_pdf > 0. {
+            base_value / base_pdf
+        } else {
+            Color::ZERO
+        };
+        let bounces = 1. / (1. - (albedo * returned).min(Color::splat(0.99)));
+        value +=
+            entering * base_value * bounces * self.transmittance(cos_up) * leaving * solid_angle;
+        pdf += under_coat * base_pdf * leaving * solid_angle / (1. - reflectance);
+        (value, pdf)
+    }
+}
+
+/// Chance of light arriving along `wo` refracting into the coat rather than reflecting off the
+/// microfacet it meets, averaged over the visible microfacets on a grid.
+fn refracted_fraction(ggx: &Ggx, wo: Vec3, eta: f64) -> f64 {
+    const STEPS: u32 = 8;
+    let reflected: f64 = itertools::iproduct!(0..STEPS, 0..STEPS)
+        .map(|(i, j)| {
+            let u1 = (i as f64 + 0.5) / STEPS as f64;
+            let u2 = (j as f64 + 0.5) / STEPS as f64;
+            fresnel_dielectric(wo.dot(ggx.visible_normal(wo, u1, u2)), eta)
+        })
+        .sum();
+    1. - reflected / (STEPS * STEPS) as f64
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "coated"))]
+impl Material for Coated {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        if wo.z <= 0. {
+            return None;
+        }
+
+        // Reflect off the coat, with the probability given by its Fresnel reflectance
+        let eta = self.ior / hit_record.exterior_ior;
+        let roughness = self.roughness.scalar(&hit_record.texture_coords());
+        let ggx = Ggx::from_roughness(roughness.clamp(0., 1.));
+        let m = ggx.sample_visible(wo, rng);
+        if rng.gen::<f64>() < fresnel_dielectric(wo.dot(m), eta) {
+            let wi = reflect(wo, m);
+            if wi.z <= 0. {
+                return None;
+            }
+            let scattered = Ray::new(hit_record.point, hit_record.from_local(wi));
+            return Some((scattered, Color::splat(ggx.g2(wo, wi) / ggx.g1(wo))));
+        }
+
+        // Otherwise refract into the coat, and bounce off the base until the light gets out
+        let normal = hit_record.normal;
+        let mut below = hit_record.clone();
+        below.exterior_ior = self.ior;
+        let mut direction = refract(ray.direction.normalize(), normal, 1. / eta);
+        let mut weight = self.transmittance(-direction.dot(normal));
+        for _ in 0..MAX_BOUNCES {
+            let mut inside = Ray::new(hit_record.point, direction);
+            inside.wavelengths = ray.wavelengths;
+            let (scattered, attenuation) = self.base.scatter(&inside, &below, rng)?;
+            weight *= attenuation;
+
+            let up = scattered.direction.normalize();
+            let cos_up = up.dot(normal);
+            if cos_up <= 0. {
+                // Transmitted through the base, like glass
+                return Some((scattered, weight));
+            }
+
+            weight *= self.transmittance(cos_up);
+            if rng.gen::<f64>() >= fresnel_dielectric(cos_up, 1. / eta) {
+                let scattered = Ray::new(hit_record.point, refract(up, -normal, eta));
+                return Some((scattered, weight));
+            }
+
+            // Reflected back down off the underside of the coat
+            direction = up.reflect(normal);
+            weight *= self.transmittance(cos_up);
+        }
+        None
+    }
+
+    fn emitted(&self, ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.base.emitted(ray, hit_record)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.eval_pdf(ray, hit_record, scattered).0
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.eval_pdf(ray, hit_record, scattered).1
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.base.medium()
+    }
+}
+
+fn zero() -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new(Color::ZERO))
+}
+
+#[cfg(feature = "serde")]
+fn default_ior() -> f64 {
+    1.5
+}
+
+fn default_thickness() -> f64 {
+    1.
+}
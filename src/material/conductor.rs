@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::microfacet::{fresnel_conductor, reflect, Ggx};
+use crate::material::{Material, ThinFilm};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::{Color, Vec3};
+
+/// Complex index of refraction `eta + ik` of a metal, per color channel.
+///
+/// In scene files this is either a table with `eta` and `k`, or the name of a preset: `"gold"`,
+/// `"copper"`, `"aluminium"` or `"silver"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ComplexIorFile"))]
+pub struct ComplexIor {
+    pub eta: Color,
+    pub k: Color,
+}
+
+impl ComplexIor {
+    // Measured values at roughly 650, 550 and 450 nm
+    pub const GOLD: Self = Self::new(
+        Color::new(0.143, 0.374, 1.442),
+        Color::new(3.983, 2.385, 1.603),
+    );
+    pub const COPPER: Self = Self::new(
+        Color::new(0.200, 0.924, 1.102),
+        Color::new(3.912, 2.452, 2.142),
+    );
+    pub const ALUMINIUM: Self = Self::new(
+        Color::new(1.657, 0.880, 0.521),
+        Color::new(9.224, 6.270, 4.837),
+    );
+    pub const SILVER: Self = Self::new(
+        Color::new(0.155, 0.117, 0.138),
+        Color::new(4.828, 3.122, 2.147),
+    );
+
+    pub const fn new(eta: Color, k: Color) -> Self {
+        Self { eta, k }
+    }
+
+    /// Look up a preset by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "gold" => Some(Self::GOLD),
+            "copper" => Some(Self::COPPER),
+            "aluminium" | "aluminum" => Some(Self::ALUMINIUM),
+            "silver" => Some(Self::SILVER),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComplexIorFile {
+    Preset(String),
+    Values { eta: Color, k: Color },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ComplexIorFile> for ComplexIor {
+    type Error = String;
+
+    fn try_from(file: ComplexIorFile) -> Result<Self, Self::Error> {
+        match file {
+            ComplexIorFile::Preset(name) => ComplexIor::preset(&name).ok_or_else(|| {
+                format!(
+                    "unknown metal `{name}`, expected one of: `gold`, `copper`, `aluminium`, `silver`"
+                )
+            }),
+            ComplexIorFile::Values { eta, k } => Ok(ComplexIor::new(eta, k)),
+        }
+    }
+}
+
+/// Rough metal: a GGX microfacet BRDF with the Fresnel reflectance of a complex index of
+/// refraction. Unlike [`Metal`](super::Metal), this conserves energy and matches measured
+/// metals, but it loses some energy at high roughness since light bouncing between
+/// microfacets isn't modelled.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Conductor {
+    pub ior: ComplexIor,
+    /// Perceptual roughness, 0 for a mirror to 1 for fully rough
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub roughness: Arc<dyn Texture>,
+    /// Coating whose interference colors reflections
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(ior: ComplexIor, roughness: f64) -> Self {
+        Self::textured(ior, Arc::new(SolidColor::new(Color::splat(roughness))))
+    }
+
+    pub fn textured(ior: ComplexIor, roughness: Arc<dyn Texture>) -> Self {
+        Self {
+            ior,
+            roughness,
+            film: None,
+        }
+    }
+
+    pub fn film(mut self, film: ThinFilm) -> Self {
+        self.film = Some(film);
+        self
+    }
+
+    fn ggx(&self, hit_record: &HitRecord) -> Ggx {
+        let roughness = self.roughness.scalar(&hit_record.texture_coords());
+        Ggx::from_roughness(roughness.clamp(0., 1.))
+    }
+
+    fn fresnel(&self, hit_record: &HitRecord, wo: Vec3, m: Vec3) -> Color {
+        match &self.film {
+            Some(film) => film.reflectance(
+                hit_record,
+                wo.dot(m),
+                hit_record.exterior_ior,
+                self.ior.eta,
+                self.ior.k,
+            ),
+            None => fresnel_conductor(wo.dot(m), self.ior.eta, self.ior.k),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "conductor"))]
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        if wo.z <= 0. {
+            return None;
+        }
+
+        let ggx = self.ggx(hit_record);
+        let m = ggx.sample_visible(wo, rng);
+        let wi = reflect(wo, m);
+        if wi.z <= 0. {
+            return None;
+        }
+
+        // D and the cosines cancel against the visible normal pdf
+        let attenuation = self.fresnel(hit_record, wo, m) * ggx.g2(wo, wi) / ggx.g1(wo);
+        let scattered = Ray::new(hit_record.point, hit_record.from_local(wi));
+        Some((scattered, attenuation))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        if wo.z <= 0. || wi.z <= 0. {
+            return Color::ZERO;
+        }
+
+        let ggx = self.ggx(hit_record);
+        let m = (wo + wi).normalize();
+        self.fresnel(hit_record, wo, m) * ggx.d(m) * ggx.g2(wo, wi) / (4. * wo.z)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        if wo.z <= 0. || wi.z <= 0. {
+            return 0.;
+        }
+
+        let m = (wo + wi).normalize();
+        // Change of variables from the half vector to the reflected direction
+        self.ggx(hit_record).visible_pdf(wo, m) / (4. * wo.dot(m))
+    }
+}
@@ -0,0 +1,263 @@
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::{FaceSide, HitRecord};
+use crate::material::microfacet::fresnel_dielectric;
+use crate::material::{reflectance, Material, ThinFilm};
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::vectors::refract;
+use crate::Color;
+
+/// Index of refraction, either constant or varying with wavelength to disperse light into its
+/// colors. Only spectral renders show dispersion; otherwise the index at
+/// [`REFERENCE_WAVELENGTH`](crate::spectrum::REFERENCE_WAVELENGTH) is used.
+///
+/// In scene files this is a number, a table with Cauchy's `a` and `b`, a table with Sellmeier's
+/// `b` and `c`, or the name of a preset glass: `"bk7"`, `"fused_silica"`, `"sf11"` or `"diamond"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged, try_from = "IorFile"))]
+pub enum Ior {
+    Constant(f64),
+    /// Cauchy's equation `n = a + b / λ²`, with the wavelength in micrometres
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// The Sellmeier equation `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)`, with the wavelength in micrometres
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Ior {
+    /// Borosilicate crown glass, the most common optical glass
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    pub const FUSED_SILICA: Self = Self::Sellmeier {
+        b: [0.6961663, 0.4079426, 0.8974794],
+        c: [0.00467914826, 0.0135120631, 97.9340025],
+    };
+    /// Dense flint glass, strongly dispersive
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [0.3306, 4.3356, 0.],
+        c: [0.030625, 0.011236, 0.],
+    };
+
+    /// Look up a preset by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "bk7" => Some(Self::BK7),
+            "fused_silica" => Some(Self::FUSED_SILICA),
+            "sf11" => Some(Self::SF11),
+            "diamond" => Some(Self::DIAMOND),
+            _ => None,
+        }
+    }
+
+    /// Index of refraction at `wavelength`, in nanometres.
+    pub fn at(&self, wavelength: f64) -> f64 {
+        let micrometres = wavelength * 1e-3;
+        let squared = micrometres * micrometres;
+        match *self {
+            Ior::Constant(ior) => ior,
+            Ior::Cauchy { a, b } => a + b / squared,
+            Ior::Sellmeier { b, c } => (1.
+                + (0..3)
+                    .map(|i| b[i] * squared / (squared - c[i]))
+                    .sum::<f64>())
+            .sqrt(),
+        }
+    }
+
+    /// Whether the index varies with wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
+
+impl From<f64> for Ior {
+    fn from(ior: f64) -> Self {
+        Ior::Constant(ior)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IorFile {
+    Constant(f64),
+    Preset(String),
+    Cauchy { a: f64, b: f64 },
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<IorFile> for Ior {
+    type Error = String;
+
+    fn try_from(file: IorFile) -> Result<Self, Self::Error> {
+        let ior = match file {
+            IorFile::Constant(ior) => Ior::Constant(ior),
+            IorFile::Preset(name) => Ior::preset(&name).ok_or_else(|| {
+                format!(
+                    "unknown glass `{name}`, expected one of: `bk7`, `fused_silica`, `sf11`, `diamond`"
+                )
+            })?,
+            IorFile::Cauchy { a, b } => Ior::Cauchy { a, b },
+            IorFile::Sellmeier { b, c } => Ior::Sellmeier { b, c },
+        };
+        let reference = ior.at(crate::spectrum::REFERENCE_WAVELENGTH);
+        if reference > 0. {
+            Ok(ior)
+        } else {
+            Err(format!(
+                "expected a positive index of refraction, found {reference}"
+            ))
+        }
+    }
+}
+
+/// How a [`Dielectric`] works out how much light it reflects rather than refracts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Fresnel {
+    /// Schlick's approximation. Cheap, but it reflects too much at grazing angles between media
+    /// with similar indices of refraction, such as ice in water.
+    #[default]
+    Schlick,
+    /// The Fresnel equations for unpolarized light
+    Exact,
+}
+
+/// Clear refractive material such as glass or water.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Dielectric {
+    pub index_of_refraction: Ior,
+    /// Where dielectrics overlap, the one with the highest priority wins
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: u32,
+    /// Fraction of light absorbed per unit of distance travelled inside, per channel. Tints the
+    /// glass more the thicker it is.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub absorption: Color,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fresnel: Fresnel,
+    /// Coating whose interference colors reflections
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub film: Option<ThinFilm>,
+}
+
+impl Dielectric {
+    pub fn new(index_of_refraction: impl Into<Ior>) -> Self {
+        Self {
+            index_of_refraction: index_of_refraction.into(),
+            priority: 0,
+            absorption: Color::ZERO,
+            fresnel: Fresnel::default(),
+            film: None,
+        }
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    pub fn fresnel(mut self, fresnel: Fresnel) -> Self {
+        self.fresnel = fresnel;
+        self
+    }
+
+    pub fn film(mut self, film: ThinFilm) -> Self {
+        self.film = Some(film);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "dielectric"))]
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let ior = self.index_of_refraction.at(ray.wavelength());
+        // Indices of refraction on the side the ray comes from and the side it goes into
+        let (near, far) = if hit_record.face_side == FaceSide::Front {
+            (hit_record.exterior_ior, ior)
+        } else {
+            (ior, hit_record.exterior_ior)
+        };
+        let refraction_ratio = near / far;
+
+        let unit_direction = ray.direction.normalize();
+        // let refracted = refract(unit_direction, hit_record.normal, refraction_ratio);
+
+        // Account for total internal reflection
+        let cos_theta = (-unit_direction).dot(hit_record.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+        let (direction, attenuation) = match &self.film {
+            // The film's reflectance differs per channel, so pick by the average and reweight
+            Some(film) if !cannot_refract => {
+                let reflectance =
+                    film.reflectance(hit_record, cos_theta, near, Color::splat(far), Color::ZERO);
+                let probability = reflectance.element_sum() / 3.;
+                if rng.gen::<f64>() < probability {
+                    (
+                        unit_direction.reflect(hit_record.normal),
+                        reflectance / probability,
+                    )
+                } else {
+                    (
+                        refract(unit_direction, hit_record.normal, refraction_ratio),
+                        (1. - reflectance) / (1. - probability),
+                    )
+                }
+            }
+            _ => {
+                let reflectance = match self.fresnel {
+                    Fresnel::Schlick => reflectance(cos_theta, refraction_ratio),
+                    Fresnel::Exact => fresnel_dielectric(cos_theta, far / near),
+                };
+                let direction = if cannot_refract || rng.gen::<f64>() < reflectance {
+                    unit_direction.reflect(hit_record.normal)
+                } else {
+                    // Can refract
+                    refract(unit_direction, hit_record.normal, refraction_ratio)
+                };
+                (direction, Color::ONE)
+            }
+        };
+
+        let scattered = Ray::new(hit_record.point, direction);
+        Some((scattered, attenuation))
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            absorption: self.absorption,
+            ..Medium::new(self.index_of_refraction, self.priority)
+        })
+    }
+}
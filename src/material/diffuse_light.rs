@@ -0,0 +1,42 @@
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::Color;
+
+/// Surface that emits light evenly in all directions and reflects none.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "diffuse_light"))]
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _hit_record: &HitRecord,
+        _rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, _ray: &Ray, _hit_record: &HitRecord) -> Color {
+        self.emit
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.emit != Color::ZERO
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::density::DensityField;
+use crate::hit_record::HitRecord;
+use crate::material::Material;
+use crate::medium::{Density, Medium};
+use crate::ray::Ray;
+use crate::{Color, Vec3};
+
+/// Smoke or cloud whose density varies from point to point, given by a density field stretched
+/// over the box from `min` to `max`. Like [`Volume`](super::Volume), the surface is invisible.
+///
+/// Usually used through [`HeterogeneousMedium`](crate::shapes::heterogeneous_medium::HeterogeneousMedium),
+/// which is bounded by the same box, but it works on any closed shape; the density is zero outside
+/// the box.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct HeterogeneousVolume {
+    pub field: Arc<dyn DensityField>,
+    pub min: Vec3,
+    pub max: Vec3,
+    /// Interactions per unit of distance where the field is 1
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "super::positive"))]
+    pub density: f64,
+    /// Fraction of interactions that scatter rather than absorb light, per channel
+    #[cfg_attr(feature = "serde", serde(default = "white"))]
+    pub albedo: Color,
+    /// Henyey–Greenstein asymmetry, from -1 (scattering backwards) through 0 (isotropic) to 1
+    /// (scattering forwards)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anisotropy: f64,
+}
+
+impl HeterogeneousVolume {
+    pub fn new(field: Arc<dyn DensityField>, min: Vec3, max: Vec3, density: f64) -> Self {
+        Self {
+            field,
+            min,
+            max,
+            density,
+            albedo: Color::ONE,
+            anisotropy: 0.,
+        }
+    }
+
+    pub fn albedo(mut self, albedo: Color) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    pub fn anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "heterogeneous_volume"))]
+impl Material for HeterogeneousVolume {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        _rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        Some((Ray::new(hit_record.point, ray.direction), Color::ONE))
+    }
+
+    fn is_invisible(&self) -> bool {
+        true
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            density: Some(Arc::new(Density {
+                field: self.field.clone(),
+                min: self.min,
+                max: self.max,
+            })),
+            ..Medium::homogeneous(self.density, self.albedo, self.anisotropy)
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn white() -> Color {
+    Color::ONE
+}
@@ -0,0 +1,67 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::vectors::random_unit_vector;
+use crate::{Color, Vec3};
+
+/// Ideal diffuse surface.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Lambertian {
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "lambertian"))]
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let mut scatter_direction = hit_record.normal + random_unit_vector(rng);
+
+        // Don't scatter near zero
+        if scatter_direction.abs_diff_eq(Vec3::ZERO, 1e-8) {
+            scatter_direction = hit_record.normal;
+        }
+
+        let scattered_ray = Ray::new(hit_record.point, scatter_direction);
+        let attenuation = self.albedo.value(&hit_record.texture_coords());
+        Some((scattered_ray, attenuation))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo.value(&hit_record.texture_coords()) * self.pdf(ray, hit_record, scattered)
+    }
+
+    fn pdf(&self, _ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        // Scattering around the normal gives a cosine-weighted hemisphere
+        let cos_theta = hit_record.normal.dot(scattered.direction.normalize());
+        cos_theta.max(0.) / PI
+    }
+
+    fn is_diffuse(&self) -> bool {
+        true
+    }
+}
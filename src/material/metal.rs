@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ThinFilm};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::vectors::random_unit_vector;
+use crate::Color;
+
+/// Mirror reflection, blurred by `fuzz`, which is clamped to `[0, 1]`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Metal {
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub albedo: Arc<dyn Texture>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "crate::texture::serde_texture::serialize",
+            deserialize_with = "super::unit_texture"
+        )
+    )]
+    pub fuzz: Arc<dyn Texture>,
+    /// Coating whose interference colors reflections, like anodized titanium. The albedo is taken
+    /// as the metal's reflectance at normal incidence underneath it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub film: Option<ThinFilm>,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::textured(
+            Arc::new(SolidColor::new(albedo)),
+            Arc::new(SolidColor::new(Color::splat(fuzz))),
+        )
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: Arc<dyn Texture>) -> Self {
+        Self {
+            albedo,
+            fuzz,
+            film: None,
+        }
+    }
+
+    pub fn film(mut self, film: ThinFilm) -> Self {
+        self.film = Some(film);
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "metal"))]
+impl Material for Metal {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let coords = hit_record.texture_coords();
+        let fuzz = self.fuzz.scalar(&coords).clamp(0., 1.);
+        let reflected = ray.direction.reflect(hit_record.normal).normalize();
+        let scattered = Ray::new(hit_record.point, reflected + fuzz * random_unit_vector(rng));
+        let albedo = self.albedo.value(&coords);
+        let attenuation = match &self.film {
+            Some(film) => {
+                // A metal with no refraction and just enough extinction to reflect its albedo
+                let albedo = albedo.clamp(Color::ZERO, Color::splat(0.999));
+                let k = 2. * (albedo / (1. - albedo)).map(f64::sqrt);
+                let cos_theta = -ray.direction.normalize().dot(hit_record.normal);
+                film.reflectance(
+                    hit_record,
+                    cos_theta,
+                    hit_record.exterior_ior,
+                    Color::ONE,
+                    k,
+                )
+            }
+            None => albedo,
+        };
+        if scattered.direction.dot(hit_record.normal) > 0. {
+            Some((scattered, attenuation))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! GGX (Trowbridge-Reitz) microfacet distribution with Smith masking-shadowing, shared by the
+//! rough materials.
+//!
+//! Directions are in the shading frame of the hit (see `HitRecord::to_local`), with the normal
+//! along Z.
+
+use std::f64::consts::PI;
+
+use rand::{Rng, RngCore};
+
+use crate::{Color, Vec3};
+
+/// Below this the distribution is too sharp to evaluate reliably
+const MIN_ALPHA: f64 = 1e-3;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ggx {
+    alpha: f64,
+}
+
+impl Ggx {
+    /// Distribution for a perceptual roughness in `0..1`, remapped to GGX's alpha as roughness
+    /// squared.
+    pub fn from_roughness(roughness: f64) -> Self {
+        Self {
+            alpha: (roughness * roughness).clamp(MIN_ALPHA, 1.),
+        }
+    }
+
+    /// Density of microfacet normals `m`, per unit projected area.
+    pub fn d(&self, m: Vec3) -> f64 {
+        if m.z <= 0. {
+            return 0.;
+        }
+        let alpha2 = self.alpha * self.alpha;
+        let denominator = m.z * m.z * (alpha2 - 1.) + 1.;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    fn lambda(&self, w: Vec3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0. {
+            return f64::INFINITY;
+        }
+        let tan2 = (1. - cos2).max(0.) / cos2;
+        0.5 * (-1. + (1. + self.alpha * self.alpha * tan2).sqrt())
+    }
+
+    /// Fraction of microfacets facing `m` that are visible from `w`.
+    pub fn g1(&self, w: Vec3) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    /// Height-correlated masking-shadowing for the pair of directions.
+    pub fn g2(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Sample a microfacet normal visible from `wo`, from the distribution of visible normals
+    /// (Heitz 2018). `wo` must be above the surface.
+    pub fn sample_visible(&self, wo: Vec3, rng: &mut dyn RngCore) -> Vec3 {
+        self.visible_normal(wo, rng.gen(), rng.gen())
+    }
+
+    /// The normal `sample_visible` picks for the uniform random numbers `u1` and `u2`, for
+    /// integrating over the visible normals on a grid.
+    pub fn visible_normal(&self, wo: Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Stretch to the hemisphere configuration
+        let vh = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).normalize();
+        let length2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length2 > 0. {
+            Vec3::new(-vh.y, vh.x, 0.) / length2.sqrt()
+        } else {
+            Vec3::X
+        };
+        let t2 = vh.cross(t1);
+
+        // Uniform disc, warped to the visible half
+        let r = u1.sqrt();
+        let phi = 2. * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1. + vh.z);
+        let p2 = (1. - s) * (1. - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1. - p1 * p1 - p2 * p2).max(0.).sqrt() * vh;
+
+        // Unstretch
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalize()
+    }
+
+    /// Density of `sample_visible` choosing `m`, per unit solid angle of `m`.
+    pub fn visible_pdf(&self, wo: Vec3, m: Vec3) -> f64 {
+        if wo.z <= 0. {
+            return 0.;
+        }
+        self.g1(wo) * wo.dot(m).max(0.) * self.d(m) / wo.z
+    }
+}
+
+/// Mirror `wo` about the microfacet normal `m`.
+pub(crate) fn reflect(wo: Vec3, m: Vec3) -> Vec3 {
+    2. * wo.dot(m) * m - wo
+}
+
+/// Refract `wo` through the microfacet normal `m`, where `eta` is the index of refraction of the
+/// far side over that of the near side. `None` on total internal reflection.
+pub(crate) fn refract(wo: Vec3, m: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = wo.dot(m);
+    let sin2_t = (1. - cos_i * cos_i).max(0.) / (eta * eta);
+    if sin2_t >= 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    Some(-wo / eta + (cos_i / eta - cos_t) * m)
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface, where `eta` is the index of
+/// refraction of the far side over that of the near side. 1 on total internal reflection.
+pub(crate) fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of refraction `eta + ik`,
+/// per channel.
+pub(crate) fn fresnel_conductor(cos_i: f64, eta: Color, k: Color) -> Color {
+    let cos2 = cos_i.clamp(0., 1.).powi(2);
+    let sin2 = 1. - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4. * eta2 * k2).map(f64::sqrt);
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(Color::ZERO).map(f64::sqrt);
+    let t2 = 2. * cos_i * a;
+    let perpendicular = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let parallel = perpendicular * (t3 - t4) / (t3 + t4);
+
+    0.5 * (parallel + perpendicular)
+}
+
+/// Microfacet normal that takes `wo` to `wi` through a dielectric interface, facing `wo`'s side.
+/// `None` for pairs no microfacet connects.
+fn dielectric_half_vector(wo: Vec3, wi: Vec3, eta: f64) -> Option<Vec3> {
+    let m = if wi.z > 0. { wo + wi } else { wo + eta * wi };
+    let m = m.normalize_or_zero();
+    let m = if m.z < 0. { -m } else { m };
+    // Both directions must be on the correct side of the microfacet
+    if m == Vec3::ZERO || wo.dot(m) <= 0. || wi.dot(m) * wi.z <= 0. {
+        return None;
+    }
+    Some(m)
+}
+
+/// Sample the rough dielectric BSDF (Walter et al. 2007), returning the scattered direction and
+/// its weight. Reflection and refraction are chosen by their Fresnel weights, which then cancel.
+pub(crate) fn sample_dielectric(
+    ggx: &Ggx,
+    wo: Vec3,
+    eta: f64,
+    rng: &mut dyn RngCore,
+) -> Option<(Vec3, f64)> {
+    if wo.z <= 0. {
+        return None;
+    }
+
+    let m = ggx.sample_visible(wo, rng);
+    let fresnel = fresnel_dielectric(wo.dot(m), eta);
+    let reflected = rng.gen::<f64>() < fresnel;
+    let wi = if reflected {
+        reflect(wo, m)
+    } else {
+        refract(wo, m, eta)?
+    };
+    // Reflections must stay above the surface and refractions go below it
+    if wi.z == 0. || (wi.z > 0.) != reflected {
+        return None;
+    }
+
+    Some((wi, ggx.g2(wo, wi) / ggx.g1(wo)))
+}
+
+/// The rough dielectric BSDF times the cosine term, and the density of `sample_dielectric`
+/// choosing `wi`.
+pub(crate) fn eval_dielectric(ggx: &Ggx, wo: Vec3, wi: Vec3, eta: f64) -> (f64, f64) {
+    if wo.z <= 0. || wi.z == 0. {
+        return (0., 0.);
+    }
+    let Some(m) = dielectric_half_vector(wo, wi, eta) else {
+        return (0., 0.);
+    };
+
+    let fresnel = fresnel_dielectric(wo.dot(m), eta);
+    let visible = ggx.visible_pdf(wo, m);
+    // The pdfs change variables from the half vector to the scattered direction
+    if wi.z > 0. {
+        let value = fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4. * wo.z);
+        let pdf = fresnel * visible / (4. * wo.dot(m));
+        (value, pdf)
+    } else {
+        let jacobian = eta * eta * wi.dot(m).abs() / (wo.dot(m) + eta * wi.dot(m)).powi(2);
+        let value = (1. - fresnel) * ggx.d(m) * ggx.g2(wo, wi) * wo.dot(m) * jacobian / wo.z;
+        let pdf = (1. - fresnel) * visible * jacobian;
+        (value, pdf)
+    }
+}
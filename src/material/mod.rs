@@ -0,0 +1,135 @@
+pub mod bump;
+pub mod coated;
+pub mod conductor;
+pub mod dielectric;
+pub mod diffuse_light;
+pub mod heterogeneous_volume;
+pub mod lambertian;
+pub mod metal;
+mod microfacet;
+pub mod principled;
+pub mod rough_dielectric;
+pub mod subsurface;
+pub mod thin_film;
+pub mod volume;
+
+use std::fmt::Debug;
+
+use rand::RngCore;
+
+use crate::hit_record::HitRecord;
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::Color;
+
+pub use bump::{BumpMap, NormalMap};
+pub use coated::Coated;
+pub use conductor::{ComplexIor, Conductor};
+pub use dielectric::{Dielectric, Fresnel, Ior};
+pub use diffuse_light::DiffuseLight;
+pub use heterogeneous_volume::HeterogeneousVolume;
+pub use lambertian::Lambertian;
+pub use metal::Metal;
+pub use principled::Principled;
+pub use rough_dielectric::RoughDielectric;
+pub use subsurface::Subsurface;
+pub use thin_film::ThinFilm;
+pub use volume::Volume;
+
+/// How a surface scatters and emits light.
+///
+/// Implement this to add new materials; shapes hold them as `Arc<dyn Material>`. With the `serde`
+/// feature, implementations also need `#[typetag::serde]` so scenes using them can be saved and
+/// loaded.
+#[cfg_attr(feature = "serde", typetag::serde(tag = "type"))]
+pub trait Material: Debug + Send + Sync {
+    /// Sample an outgoing ray, returning it with its attenuation (the BSDF times the cosine term,
+    /// divided by the sampling pdf). `None` means the ray is absorbed.
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)>;
+
+    /// Light given off by the surface, independent of any incoming light.
+    fn emitted(&self, _ray: &Ray, _hit_record: &HitRecord) -> Color {
+        Color::ZERO
+    }
+
+    /// Whether `emitted` can be non-zero. Shapes with emissive materials are sampled directly as
+    /// lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// The BSDF times the cosine term for light arriving along `scattered` and leaving back along
+    /// `ray`. Zero for perfectly specular materials, which can only be sampled with `scatter`.
+    fn eval(&self, _ray: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Color {
+        Color::ZERO
+    }
+
+    /// Probability density of `scatter` choosing `scattered`, per unit solid angle.
+    fn pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        0.
+    }
+
+    /// Whether the surface lets light straight through unchanged, like the boundary of a volume.
+    /// Rays, shadow rays included, carry on through it into the medium inside.
+    fn is_invisible(&self) -> bool {
+        false
+    }
+
+    /// Whether light is scattered the same way whichever way it arrives, with a cosine-weighted
+    /// `pdf` about the normal. Layered materials can only work out `eval` and `pdf` over bases
+    /// like this.
+    fn is_diffuse(&self) -> bool {
+        false
+    }
+
+    /// The medium inside the surface, for materials that let light through. Rays that refract
+    /// into it are tracked as being inside, see [`crate::medium`].
+    fn medium(&self) -> Option<Medium> {
+        None
+    }
+}
+
+fn reflectance(cosine: f64, index_of_refraction: f64) -> f64 {
+    // Use Schlick's approximation for reflectance
+    let r0 = ((1. - index_of_refraction) / (1. + index_of_refraction)).powi(2);
+    r0 + (1. - r0) * (1. - cosine).powi(5)
+}
+
+/// Deserialize a texture whose value must lie in `[0, 1]`. Only constant values can be checked
+/// here; textures are clamped where they're sampled.
+#[cfg(feature = "serde")]
+fn unit_texture<'de, D>(
+    deserializer: D,
+) -> Result<std::sync::Arc<dyn crate::texture::Texture>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let texture = crate::texture::serde_texture::deserialize(deserializer)?;
+    match texture.constant() {
+        Some(color) if !color.cmpge(Color::ZERO).all() || !color.cmple(Color::ONE).all() => Err(
+            serde::de::Error::custom(format!("expected a value between 0 and 1, found {color}")),
+        ),
+        _ => Ok(texture),
+    }
+}
+
+/// Deserialize a value that must be greater than zero.
+#[cfg(feature = "serde")]
+fn positive<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <f64 as serde::Deserialize>::deserialize(deserializer)?;
+    if value > 0. {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "expected a positive value, found {value}"
+        )))
+    }
+}
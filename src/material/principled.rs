@@ -0,0 +1,368 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::{FaceSide, HitRecord};
+use crate::material::microfacet::{eval_dielectric, reflect, sample_dielectric, Ggx};
+use crate::material::{Ior, Material};
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::texture::{Channel, ColorChannel, SolidColor, Texture};
+use crate::vectors::random_cosine_direction;
+use crate::{Color, Vec3};
+
+/// One material for most real surfaces, in the spirit of Disney's principled BSDF, with the
+/// parameters of glTF's metallic-roughness model and its common extensions.
+///
+/// It blends three bases: an opaque dielectric (diffuse plus a GGX specular lobe), a metal
+/// tinted by `base_color`, and a rough transmissive dielectric, with an optional sheen on the
+/// diffuse part and a clear coat over everything. Every lobe is evaluated in `eval` and `pdf`,
+/// and `scatter` weights its sample by the combined BSDF over the combined pdf.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Principled {
+    /// Diffuse albedo for dielectrics, reflectance at normal incidence for metals, and the tint
+    /// of transmitted light (glTF `baseColor`)
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub base_color: Arc<dyn Texture>,
+    /// 0 for dielectrics to 1 for metals (glTF `metallic`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::texture::serde_texture", default = "zero")
+    )]
+    pub metallic: Arc<dyn Texture>,
+    /// Perceptual roughness, 0 for a mirror to 1 for fully rough (glTF `roughness`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::texture::serde_texture", default = "half")
+    )]
+    pub roughness: Arc<dyn Texture>,
+    /// Scales the dielectric specular reflectance given by `ior` (`KHR_materials_specular`)
+    #[cfg_attr(feature = "serde", serde(default = "default_specular"))]
+    pub specular: f64,
+    /// Tints dielectric specular reflections towards the hue of `base_color`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub specular_tint: f64,
+    /// Soft velvet-like reflection at grazing angles, for cloth
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sheen: f64,
+    /// Tints the sheen towards the hue of `base_color`
+    #[cfg_attr(feature = "serde", serde(default = "default_sheen_tint"))]
+    pub sheen_tint: f64,
+    /// Strength of a clear, colorless coat on top (`KHR_materials_clearcoat`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clearcoat: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_clearcoat_roughness"))]
+    pub clearcoat_roughness: f64,
+    /// 0 for opaque to 1 for fully transmissive dielectrics (`KHR_materials_transmission`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub transmission: f64,
+    /// Index of refraction of the dielectric parts (`KHR_materials_ior`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_ior", deserialize_with = "super::positive")
+    )]
+    pub ior: f64,
+    /// Where transmissive materials overlap, the one with the highest priority wins
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: u32,
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(base_color)))
+    }
+
+    pub fn textured(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: zero(),
+            roughness: half(),
+            specular: default_specular(),
+            specular_tint: 0.,
+            sheen: 0.,
+            sheen_tint: default_sheen_tint(),
+            clearcoat: 0.,
+            clearcoat_roughness: default_clearcoat_roughness(),
+            transmission: 0.,
+            ior: default_ior(),
+            priority: 0,
+        }
+    }
+
+    /// Map a glTF 2.0 metallic-roughness material. `base_color` and `metallic_roughness` are the
+    /// glTF textures with their factors applied (or just the factors, as solid colors). As in
+    /// glTF, roughness is read from the green channel and metallic from the blue one.
+    pub fn from_gltf(base_color: Arc<dyn Texture>, metallic_roughness: Arc<dyn Texture>) -> Self {
+        let mut principled = Self::textured(base_color);
+        principled.roughness = Arc::new(Channel::new(
+            metallic_roughness.clone(),
+            ColorChannel::Green,
+        ));
+        principled.metallic = Arc::new(Channel::new(metallic_roughness, ColorChannel::Blue));
+        principled
+    }
+
+    pub fn metallic(mut self, metallic: f64) -> Self {
+        self.metallic = Arc::new(SolidColor::new(Color::splat(metallic)));
+        self
+    }
+
+    pub fn roughness(mut self, roughness: f64) -> Self {
+        self.roughness = Arc::new(SolidColor::new(Color::splat(roughness)));
+        self
+    }
+
+    pub fn specular(mut self, specular: f64, tint: f64) -> Self {
+        self.specular = specular;
+        self.specular_tint = tint;
+        self
+    }
+
+    pub fn sheen(mut self, sheen: f64, tint: f64) -> Self {
+        self.sheen = sheen;
+        self.sheen_tint = tint;
+        self
+    }
+
+    pub fn clearcoat(mut self, clearcoat: f64, roughness: f64) -> Self {
+        self.clearcoat = clearcoat;
+        self.clearcoat_roughness = roughness;
+        self
+    }
+
+    pub fn transmission(mut self, transmission: f64) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    pub fn ior(mut self, ior: f64) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Look up the textures and work out the lobes at a hit.
+    fn lobes(&self, hit_record: &HitRecord) -> Lobes {
+        let coords = hit_record.texture_coords();
+        let base_color = self.base_color.value(&coords);
+        let metallic = self.metallic.scalar(&coords).clamp(0., 1.);
+        let roughness = self.roughness.scalar(&coords).clamp(0., 1.);
+        let transmission = self.transmission.clamp(0., 1.);
+
+        let luminance = base_color.dot(Color::new(0.2126, 0.7152, 0.0722));
+        let tint = if luminance > 0. {
+            base_color / luminance
+        } else {
+            Color::ONE
+        };
+
+        let f0 = ((self.ior - 1.) / (self.ior + 1.)).powi(2);
+        let dielectric_f0 =
+            (f0 * self.specular * Color::ONE.lerp(tint, self.specular_tint)).min(Color::ONE);
+
+        let diffuse = (1. - metallic) * (1. - transmission);
+        let transmissive = (1. - metallic) * transmission;
+        let clearcoat = self.clearcoat.clamp(0., 1.);
+
+        // Rough guesses at how much each lobe contributes, to pick which one to sample
+        let weights = [
+            0.5 * diffuse,
+            metallic + 0.5 * diffuse,
+            transmissive,
+            0.25 * clearcoat,
+        ];
+        let total: f64 = weights.iter().sum();
+
+        Lobes {
+            base_color,
+            diffuse,
+            transmissive,
+            opaque: metallic + diffuse,
+            specular_f0: metallic * base_color + diffuse * dielectric_f0,
+            dielectric_f0: dielectric_f0.max_element(),
+            sheen: self.sheen * Color::ONE.lerp(tint, self.sheen_tint),
+            clearcoat,
+            specular: Ggx::from_roughness(roughness),
+            coat: Ggx::from_roughness(self.clearcoat_roughness.clamp(0., 1.)),
+            eta: match hit_record.face_side {
+                FaceSide::Front => self.ior / hit_record.exterior_ior,
+                FaceSide::Back => hit_record.exterior_ior / self.ior,
+            },
+            probabilities: weights.map(|weight| weight / total),
+        }
+    }
+}
+
+/// The principled BSDF at one point, with its textures looked up.
+struct Lobes {
+    base_color: Color,
+    /// Weights of the opaque and transmissive dielectric bases
+    diffuse: f64,
+    transmissive: f64,
+    /// Share of the surface that isn't transmissive, which the specular lobe reflects off
+    opaque: f64,
+    /// Reflectance at normal incidence of the combined specular lobe, already weighted by base
+    specular_f0: Color,
+    dielectric_f0: f64,
+    sheen: Color,
+    clearcoat: f64,
+    specular: Ggx,
+    coat: Ggx,
+    eta: f64,
+    /// Chance of sampling the diffuse, specular, transmission and clear coat lobes
+    probabilities: [f64; 4],
+}
+
+impl Lobes {
+    /// BSDF times the cosine term, and the combined pdf of sampling `wi`.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> (Color, f64) {
+        let [p_diffuse, p_specular, p_transmission, p_coat] = self.probabilities;
+        let mut value = Color::ZERO;
+        let mut pdf = 0.;
+
+        // Light reflected by the coat doesn't reach the layers underneath
+        let coat_fresnel = self.clearcoat * schlick(0.04, wo.z);
+        let under_coat = 1. - coat_fresnel;
+
+        if wi.z > 0. {
+            let m = (wo + wi).normalize();
+
+            // Diffuse with sheen, minus what the specular layer reflects
+            let diffuse = self.diffuse * (1. - schlick(self.dielectric_f0, wo.z)) * self.base_color
+                / PI
+                + self.diffuse * self.sheen * (1. - wi.dot(m)).max(0.).powi(5) / PI;
+            value += under_coat * diffuse * wi.z;
+            pdf += p_diffuse * wi.z / PI;
+
+            let reflectance = self.specular_f0
+                + (Color::splat(self.opaque) - self.specular_f0) * schlick_weight(wo.dot(m));
+            value += under_coat * reflectance * self.specular.d(m) * self.specular.g2(wo, wi)
+                / (4. * wo.z);
+            pdf += p_specular * self.specular.visible_pdf(wo, m) / (4. * wo.dot(m));
+
+            let coat =
+                self.clearcoat * schlick(0.04, wo.dot(m)) * self.coat.d(m) * self.coat.g2(wo, wi)
+                    / (4. * wo.z);
+            value += Color::splat(coat);
+            pdf += p_coat * self.coat.visible_pdf(wo, m) / (4. * wo.dot(m));
+        }
+
+        if self.transmissive > 0. {
+            let (dielectric, dielectric_pdf) = eval_dielectric(&self.specular, wo, wi, self.eta);
+            // Only light passing through is tinted, reflections off glass are white
+            let tint = if wi.z < 0. {
+                self.base_color
+            } else {
+                Color::ONE
+            };
+            value += under_coat * self.transmissive * dielectric * tint;
+            pdf += p_transmission * dielectric_pdf;
+        }
+
+        (value, pdf)
+    }
+
+    fn sample(&self, wo: Vec3, rng: &mut dyn RngCore) -> Option<Vec3> {
+        let [p_diffuse, p_specular, p_transmission, _] = self.probabilities;
+        let choice = rng.gen::<f64>();
+        let wi = if choice < p_diffuse {
+            random_cosine_direction(rng)
+        } else if choice < p_diffuse + p_specular {
+            reflect(wo, self.specular.sample_visible(wo, rng))
+        } else if choice < p_diffuse + p_specular + p_transmission {
+            sample_dielectric(&self.specular, wo, self.eta, rng)?.0
+        } else {
+            reflect(wo, self.coat.sample_visible(wo, rng))
+        };
+        (wi.z != 0.).then_some(wi)
+    }
+}
+
+/// Schlick's approximation of Fresnel reflectance.
+fn schlick(f0: f64, cos_theta: f64) -> f64 {
+    f0 + (1. - f0) * schlick_weight(cos_theta)
+}
+
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1. - cos_theta.clamp(0., 1.)).powi(5)
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "principled"))]
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        if wo.z <= 0. {
+            return None;
+        }
+
+        let lobes = self.lobes(hit_record);
+        let wi = lobes.sample(wo, rng)?;
+        let (value, pdf) = lobes.eval(wo, wi);
+        if pdf <= 0. {
+            return None;
+        }
+
+        let scattered = Ray::new(hit_record.point, hit_record.from_local(wi));
+        Some((scattered, value / pdf))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        if wo.z <= 0. {
+            return Color::ZERO;
+        }
+        self.lobes(hit_record).eval(wo, wi).0
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        if wo.z <= 0. {
+            return 0.;
+        }
+        self.lobes(hit_record).eval(wo, wi).1
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        (self.transmission > 0.).then_some(Medium::new(Ior::Constant(self.ior), self.priority))
+    }
+}
+
+fn zero() -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new(Color::ZERO))
+}
+
+fn half() -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new(Color::splat(0.5)))
+}
+
+fn default_specular() -> f64 {
+    1.
+}
+
+fn default_sheen_tint() -> f64 {
+    0.5
+}
+
+fn default_clearcoat_roughness() -> f64 {
+    0.03
+}
+
+fn default_ior() -> f64 {
+    1.5
+}
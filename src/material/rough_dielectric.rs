@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::{FaceSide, HitRecord};
+use crate::material::microfacet::{eval_dielectric, sample_dielectric, Ggx};
+use crate::material::{Ior, Material};
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::Color;
+
+/// Frosted glass: a GGX microfacet BSDF that both reflects and refracts (Walter et al. 2007).
+/// Like [`Dielectric`](super::Dielectric), dispersive glasses split light into its colors in
+/// spectral renders.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct RoughDielectric {
+    pub index_of_refraction: Ior,
+    /// Perceptual roughness, 0 for clear glass to 1 for fully frosted
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub roughness: Arc<dyn Texture>,
+    /// Where dielectrics overlap, the one with the highest priority wins
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: u32,
+    /// Fraction of light absorbed per unit of distance travelled inside, per channel. Tints the
+    /// glass more the thicker it is.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: impl Into<Ior>, roughness: f64) -> Self {
+        Self::textured(
+            index_of_refraction,
+            Arc::new(SolidColor::new(Color::splat(roughness))),
+        )
+    }
+
+    pub fn textured(index_of_refraction: impl Into<Ior>, roughness: Arc<dyn Texture>) -> Self {
+        Self {
+            index_of_refraction: index_of_refraction.into(),
+            roughness,
+            priority: 0,
+            absorption: Color::ZERO,
+        }
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    fn ggx(&self, hit_record: &HitRecord) -> Ggx {
+        let roughness = self.roughness.scalar(&hit_record.texture_coords());
+        Ggx::from_roughness(roughness.clamp(0., 1.))
+    }
+
+    /// Index of refraction of the far side over the near side, at the ray's wavelength
+    fn eta(&self, ray: &Ray, hit_record: &HitRecord) -> f64 {
+        let ior = self.index_of_refraction.at(ray.wavelength());
+        if hit_record.face_side == FaceSide::Front {
+            ior / hit_record.exterior_ior
+        } else {
+            hit_record.exterior_ior / ior
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "rough_dielectric"))]
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let (wi, weight) =
+            sample_dielectric(&self.ggx(hit_record), wo, self.eta(ray, hit_record), rng)?;
+        let scattered = Ray::new(hit_record.point, hit_record.from_local(wi));
+        Some((scattered, Color::splat(weight)))
+    }
+
+    fn eval(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        let (value, _) = eval_dielectric(&self.ggx(hit_record), wo, wi, self.eta(ray, hit_record));
+        Color::splat(value)
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let wo = hit_record.to_local(-ray.direction.normalize());
+        let wi = hit_record.to_local(scattered.direction.normalize());
+        let (_, pdf) = eval_dielectric(&self.ggx(hit_record), wo, wi, self.eta(ray, hit_record));
+        pdf
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            absorption: self.absorption,
+            ..Medium::new(self.index_of_refraction, self.priority)
+        })
+    }
+}
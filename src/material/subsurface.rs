@@ -0,0 +1,94 @@
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::{Dielectric, Fresnel, Ior, Material};
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::Color;
+
+/// A translucent material that light scatters around inside before getting back out, like skin,
+/// marble, wax or milk.
+///
+/// The surface is a smooth dielectric. Inside, the camera follows light on a random walk through
+/// the volume, so the shape needs to be closed, and deep bounce limits give softer, brighter
+/// results. Coefficients are per unit of distance, so they depend on the scale of the scene.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Subsurface {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_ior", deserialize_with = "super::positive")
+    )]
+    pub ior: f64,
+    /// Fraction of light scattered per unit of distance travelled inside, per channel
+    pub scattering: Color,
+    /// Fraction of light absorbed per unit of distance travelled inside, per channel
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub absorption: Color,
+    /// Henyey–Greenstein asymmetry, from -1 (scattering backwards) to 1 (scattering forwards)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anisotropy: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: u32,
+}
+
+impl Subsurface {
+    pub fn new(ior: f64, scattering: Color, absorption: Color) -> Self {
+        Self {
+            ior,
+            scattering,
+            absorption,
+            anisotropy: 0.,
+            priority: 0,
+        }
+    }
+
+    /// A material whose light scatters with probability `albedo` at each interaction, and travels
+    /// `mean_free_path` on average between them. Multiple scattering makes the result look
+    /// lighter than `albedo`.
+    pub fn from_albedo(ior: f64, albedo: Color, mean_free_path: f64) -> Self {
+        let extinction = 1. / mean_free_path;
+        Self::new(ior, albedo * extinction, (1. - albedo) * extinction)
+    }
+
+    pub fn anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "subsurface"))]
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        Dielectric::new(self.ior)
+            .fresnel(Fresnel::Exact)
+            .scatter(ray, hit_record, rng)
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            absorption: self.absorption,
+            scattering: self.scattering,
+            anisotropy: self.anisotropy,
+            ..Medium::new(Ior::Constant(self.ior), self.priority)
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_ior() -> f64 {
+    1.4
+}
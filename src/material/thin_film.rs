@@ -0,0 +1,163 @@
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::texture::{SolidColor, Texture};
+use crate::Color;
+
+/// Wavelengths the color channels stand for, in nanometres
+const CHANNEL_WAVELENGTHS: [f64; 3] = [650., 550., 450.];
+
+/// A transparent layer a few hundred nanometres thick on a surface, like a soap film, an oil slick
+/// or a lens coating. Light reflecting off its top and bottom interferes, coloring reflections
+/// depending on the thickness and the viewing angle.
+///
+/// Interference is worked out per color channel, at roughly 650, 550 and 450 nm.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ThinFilm {
+    /// Thickness in nanometres. Vary it with a texture for the swirls of a soap bubble.
+    #[cfg_attr(feature = "serde", serde(with = "crate::texture::serde_texture"))]
+    pub thickness: Arc<dyn Texture>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "super::positive"))]
+    pub ior: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, ior: f64) -> Self {
+        Self::textured(Arc::new(SolidColor::new(Color::splat(thickness))), ior)
+    }
+
+    pub fn textured(thickness: Arc<dyn Texture>, ior: f64) -> Self {
+        Self { thickness, ior }
+    }
+
+    /// Fresnel reflectance of the film on a substrate with complex index of refraction
+    /// `eta + ik`, for light arriving from a medium of index `outside` at `cos_i` to the normal.
+    /// The Airy sum over reflections inside the film, averaged over both polarizations.
+    pub(crate) fn reflectance(
+        &self,
+        hit_record: &HitRecord,
+        cos_i: f64,
+        outside: f64,
+        eta: Color,
+        k: Color,
+    ) -> Color {
+        let thickness = self.thickness.scalar(&hit_record.texture_coords()).max(0.);
+        let cos_i = cos_i.clamp(1e-6, 1.);
+        let sin2 = outside * outside * (1. - cos_i * cos_i);
+
+        let n1 = Complex::real(outside);
+        let n2 = Complex::real(self.ior);
+        // n cos(theta) in each layer, from Snell's law. Imaginary where light can't propagate.
+        let q1 = Complex::real(outside * cos_i);
+        let q2 = (n2 * n2 - Complex::real(sin2)).sqrt();
+
+        Color::from_array(std::array::from_fn(|channel| {
+            let n3 = Complex::new(eta[channel], k[channel]);
+            let q3 = (n3 * n3 - Complex::real(sin2)).sqrt();
+
+            // Phase difference of a round trip through the film
+            let phase = q2 * (4. * std::f64::consts::PI * thickness / CHANNEL_WAVELENGTHS[channel]);
+            let delay = (phase * Complex::I).exp();
+
+            let airy = |r12: Complex, r23: Complex| {
+                let r = (r12 + r23 * delay) / (Complex::real(1.) + r12 * r23 * delay);
+                r.norm_sqr().min(1.)
+            };
+            let perpendicular = airy((q1 - q2) / (q1 + q2), (q2 - q3) / (q2 + q3));
+            let parallel = airy(
+                (n2 * n2 * q1 - n1 * n1 * q2) / (n2 * n2 * q1 + n1 * n1 * q2),
+                (n3 * n3 * q2 - n2 * n2 * q3) / (n3 * n3 * q2 + n2 * n2 * q3),
+            );
+            0.5 * (perpendicular + parallel)
+        }))
+    }
+}
+
+/// Just enough complex arithmetic for the Fresnel equations.
+#[derive(Clone, Copy, Debug)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    const I: Self = Self::new(0., 1.);
+
+    const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    const fn real(re: f64) -> Self {
+        Self::new(re, 0.)
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root, with a non-negative imaginary part for the decaying wave
+    fn sqrt(self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = (0.5 * (norm + self.re)).max(0.).sqrt();
+        let im = (0.5 * (norm - self.re)).max(0.).sqrt();
+        Self::new(re, if self.im < 0. { -im } else { im })
+    }
+
+    fn exp(self) -> Self {
+        let magnitude = self.re.exp();
+        Self::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Self;
+
+    fn mul(self, scale: f64) -> Self {
+        Self::new(self.re * scale, self.im * scale)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let denominator = other.norm_sqr();
+        Self::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+}
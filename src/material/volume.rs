@@ -0,0 +1,74 @@
+use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::hit_record::HitRecord;
+use crate::material::Material;
+use crate::medium::Medium;
+use crate::ray::Ray;
+use crate::Color;
+
+/// Smoke, mist or cloud of constant density filling a shape. The shape's surface is invisible:
+/// rays pass straight through it, and scatter off the particles inside at random distances.
+///
+/// Works on any closed shape; see [`ConstantMedium`](crate::shapes::constant_medium::ConstantMedium)
+/// to turn an existing one into a volume.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Volume {
+    /// Interactions per unit of distance
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "super::positive"))]
+    pub density: f64,
+    /// Fraction of interactions that scatter rather than absorb light, per channel
+    #[cfg_attr(feature = "serde", serde(default = "white"))]
+    pub albedo: Color,
+    /// Henyey–Greenstein asymmetry, from -1 (scattering backwards) through 0 (isotropic) to 1
+    /// (scattering forwards)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anisotropy: f64,
+}
+
+impl Volume {
+    pub fn new(density: f64, albedo: Color) -> Self {
+        Self {
+            density,
+            albedo,
+            anisotropy: 0.,
+        }
+    }
+
+    pub fn anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "volume"))]
+impl Material for Volume {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        _rng: &mut dyn RngCore,
+    ) -> Option<(Ray, Color)> {
+        Some((Ray::new(hit_record.point, ray.direction), Color::ONE))
+    }
+
+    fn is_invisible(&self) -> bool {
+        true
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium::homogeneous(
+            self.density,
+            self.albedo,
+            self.anisotropy,
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn white() -> Color {
+    Color::ONE
+}
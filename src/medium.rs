@@ -0,0 +1,402 @@
+//! Tracking which refractive media a ray is inside, for nested dielectrics such as ice in water or
+//! liquid in a glass.
+//!
+//! Each ray carries a [`MediumStack`] of the transmissive materials it has entered. When it hits
+//! one, the camera works out the index of refraction on the other side of the surface from the
+//! stack and stores it in [`HitRecord::exterior_ior`](crate::hit_record::HitRecord) for the
+//! material.
+//!
+//! Overlapping geometry, like a liquid modelled slightly larger than the inside of its glass, is
+//! resolved by priority (Schmidt and Budge 2002): inside a medium, surfaces of media with a lower
+//! priority are ignored, and the ray passes straight through them.
+//!
+//! Media can also absorb light, so the camera dims rays by the distance they travel through them.
+//! Media that scatter light, like skin, milk or smoke, are rendered with a random walk: the camera
+//! samples how far a ray gets before it scatters ([`sample_flight`]), and picks a new direction
+//! from the phase function ([`sample_phase`]). The same goes for smoke and clouds inside a
+//! [`Volume`](crate::material::Volume), and [`Fog`] filling the world. Heterogeneous media, whose
+//! [`Density`] varies from point to point, are tracked with [`track_flight`] instead.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::density::DensityField;
+use crate::material::Ior;
+use crate::{Color, Vec3};
+
+/// Optical properties of the inside of a transmissive material.
+#[derive(Clone, Debug)]
+pub struct Medium {
+    pub ior: Ior,
+    /// Higher priorities win where media overlap
+    pub priority: u32,
+    /// Fraction of light absorbed per unit of distance travelled, per channel
+    pub absorption: Color,
+    /// Fraction of light scattered per unit of distance travelled, per channel
+    pub scattering: Color,
+    /// Henyey–Greenstein asymmetry of scattering, from -1 (all backwards) through 0 (the same in
+    /// every direction) to 1 (all forwards)
+    pub anisotropy: f64,
+    /// Scales absorption and scattering from point to point, for heterogeneous media
+    pub density: Option<Arc<Density>>,
+}
+
+impl Medium {
+    /// A clear medium, which neither absorbs nor scatters light.
+    pub fn new(ior: Ior, priority: u32) -> Self {
+        Self {
+            ior,
+            priority,
+            absorption: Color::ZERO,
+            scattering: Color::ZERO,
+            anisotropy: 0.,
+            density: None,
+        }
+    }
+
+    /// A medium of particles like smoke or water droplets, with no surface of its own to refract
+    /// light. `density` is how many interactions there are per unit of distance, and `albedo` the
+    /// fraction of them that scatter rather than absorb light.
+    pub fn homogeneous(density: f64, albedo: Color, anisotropy: f64) -> Self {
+        Self {
+            absorption: density * (1. - albedo),
+            scattering: density * albedo,
+            anisotropy,
+            ..Self::new(Ior::Constant(1.), 0)
+        }
+    }
+
+    /// Whether light can scatter inside, rather than only be absorbed.
+    pub fn scatters(&self) -> bool {
+        self.scattering != Color::ZERO
+    }
+}
+
+/// A density field stretched over a box. Outside the box the density is zero.
+#[derive(Clone, Debug)]
+pub struct Density {
+    pub field: Arc<dyn DensityField>,
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Density {
+    pub fn at(&self, point: Vec3) -> f64 {
+        let local = (point - self.min) / (self.max - self.min);
+        if local.cmplt(Vec3::ZERO).any() || local.cmpgt(Vec3::ONE).any() {
+            0.
+        } else {
+            self.field.density(local)
+        }
+    }
+}
+
+/// Homogeneous fog filling the whole world, outside of every shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Fog {
+    /// Interactions per unit of distance
+    pub density: f64,
+    /// Fraction of interactions that scatter rather than absorb light, per channel
+    #[cfg_attr(feature = "serde", serde(default = "white"))]
+    pub albedo: Color,
+    /// Henyey–Greenstein asymmetry of scattering, 0 for the same in every direction
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anisotropy: f64,
+}
+
+impl Fog {
+    pub fn new(density: f64, albedo: Color) -> Self {
+        Self {
+            density,
+            albedo,
+            anisotropy: 0.,
+        }
+    }
+
+    pub fn anisotropy(mut self, anisotropy: f64) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    pub fn medium(&self) -> Medium {
+        Medium::homogeneous(self.density, self.albedo, self.anisotropy)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn white() -> Color {
+    Color::ONE
+}
+
+/// Where a ray travelling through a scattering medium ends up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flight {
+    /// Scattered after `distance`
+    Scattered { distance: f64, weight: Color },
+    /// Made it the whole way without scattering
+    Passed { weight: Color },
+    /// Absorbed on the way
+    Absorbed,
+}
+
+/// Sample how far a ray gets through a medium with the given coefficients before it scatters, up
+/// to `max_distance`. The weight accounts for absorption along the way.
+///
+/// Where the coefficients differ by channel, the distance is sampled for a random channel and
+/// weighted by the average pdf over all of them.
+pub fn sample_flight(
+    absorption: Color,
+    scattering: Color,
+    max_distance: f64,
+    rng: &mut dyn RngCore,
+) -> Flight {
+    let extinction = absorption + scattering;
+    let channel = rng.gen_range(0..3);
+    let distance = -(1. - rng.gen::<f64>()).ln() / extinction[channel];
+
+    if distance < max_distance {
+        let transmittance = transmittance(extinction, distance);
+        let pdf = (extinction * transmittance).element_sum() / 3.;
+        Flight::Scattered {
+            distance,
+            weight: scattering * transmittance / pdf,
+        }
+    } else {
+        let transmittance = transmittance(extinction, max_distance);
+        let pdf = transmittance.element_sum() / 3.;
+        Flight::Passed {
+            weight: transmittance / pdf,
+        }
+    }
+}
+
+/// Like [`sample_flight`], for a heterogeneous medium whose coefficients are scaled by `density`,
+/// along a ray from `origin` in the unit `direction`.
+///
+/// Uses delta tracking: the ray takes steps as if the density were at its maximum everywhere, and
+/// at each step scatters, is absorbed, or carries on as if nothing happened, in proportion to the
+/// coefficients there. Colored coefficients are handled by picking by their average and
+/// reweighting.
+pub fn track_flight(
+    density: &Density,
+    origin: Vec3,
+    direction: Vec3,
+    absorption: Color,
+    scattering: Color,
+    max_distance: f64,
+    rng: &mut dyn RngCore,
+) -> Flight {
+    let majorant = density.field.max_density() * (absorption + scattering).max_element();
+    if majorant <= 0. {
+        return Flight::Passed { weight: Color::ONE };
+    }
+
+    let mut weight = Color::ONE;
+    let mut distance = 0.;
+    loop {
+        distance -= (1. - rng.gen::<f64>()).ln() / majorant;
+        if distance >= max_distance {
+            return Flight::Passed { weight };
+        }
+
+        let here = density.at(origin + distance * direction);
+        let absorbed = here * absorption;
+        let scattered = here * scattering;
+        let null = majorant - absorbed - scattered;
+        let p_absorbed = absorbed.element_sum() / (3. * majorant);
+        let p_scattered = scattered.element_sum() / (3. * majorant);
+
+        let u = rng.gen::<f64>();
+        if u < p_absorbed {
+            return Flight::Absorbed;
+        } else if u < p_absorbed + p_scattered {
+            return Flight::Scattered {
+                distance,
+                weight: weight * scattered / (majorant * p_scattered),
+            };
+        }
+        weight *= null / (majorant * (1. - p_absorbed - p_scattered));
+    }
+}
+
+/// Estimate the fraction of light left after `max_distance` through a heterogeneous medium whose
+/// extinction is scaled by `density`, along a ray from `origin` in the unit `direction`.
+///
+/// Uses ratio tracking: the ray takes steps as in [`track_flight`], and at each one the estimate
+/// is scaled by the chance that nothing happened there.
+pub fn ratio_tracking(
+    density: &Density,
+    origin: Vec3,
+    direction: Vec3,
+    extinction: Color,
+    max_distance: f64,
+    rng: &mut dyn RngCore,
+) -> Color {
+    let majorant = density.field.max_density() * extinction.max_element();
+    if majorant <= 0. {
+        return Color::ONE;
+    }
+
+    let mut transmittance = Color::ONE;
+    let mut distance = 0.;
+    loop {
+        distance -= (1. - rng.gen::<f64>()).ln() / majorant;
+        if distance >= max_distance {
+            return transmittance;
+        }
+        let here = density.at(origin + distance * direction);
+        transmittance *= 1. - here * extinction / majorant;
+    }
+}
+
+/// Fraction of light left after `distance` through a medium with the given extinction, per channel.
+/// Handles infinite distances through channels that don't interact.
+pub fn transmittance(extinction: Color, distance: f64) -> Color {
+    extinction.map(|sigma| {
+        if sigma == 0. {
+            1.
+        } else {
+            (-sigma * distance).exp()
+        }
+    })
+}
+
+/// Sample a direction to scatter a ray travelling along `direction` into, from the
+/// Henyey–Greenstein phase function with asymmetry `anisotropy`. The phase function is sampled
+/// exactly, so the weight is 1.
+pub fn sample_phase(direction: Vec3, anisotropy: f64, rng: &mut dyn RngCore) -> Vec3 {
+    let g = anisotropy.clamp(-0.999, 0.999);
+    let u: f64 = rng.gen();
+    let cos_theta = if g.abs() < 1e-3 {
+        1. - 2. * u
+    } else {
+        let square = (1. - g * g) / (1. + g - 2. * g * u);
+        (1. + g * g - square * square) / (2. * g)
+    };
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * rng.gen::<f64>();
+
+    let w = direction.normalize();
+    let u = w.any_orthonormal_vector();
+    let v = w.cross(u);
+    sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * w
+}
+
+/// How many media a ray can be nested in. Entering more than this is ignored.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Clone, Debug, Default)]
+struct Entry {
+    /// Identifies the material, so leaving it removes the right entry
+    id: usize,
+    medium: Option<Medium>,
+}
+
+/// The media a ray is inside, innermost last. Empty means air.
+#[derive(Debug, Default)]
+pub struct MediumStack {
+    entries: [Entry; MAX_DEPTH],
+    len: usize,
+}
+
+impl Clone for MediumStack {
+    // Rays are created at every bounce, so only copy the entries in use
+    fn clone(&self) -> Self {
+        let mut stack = Self::default();
+        stack.entries[..self.len].clone_from_slice(&self.entries[..self.len]);
+        stack.len = self.len;
+        stack
+    }
+}
+
+impl MediumStack {
+    /// A stack for rays starting in `medium`, which they never leave.
+    pub fn surrounded_by(medium: Medium) -> Self {
+        let mut stack = Self::default();
+        // No material lives at address 0, so no surface can remove it
+        stack.push(0, medium);
+        stack
+    }
+
+    /// The medium the ray is travelling through: the highest priority one, or the innermost among
+    /// equals.
+    pub fn current(&self) -> Option<&Medium> {
+        self.current_without(None)
+    }
+
+    /// Like [`MediumStack::current`], ignoring the entry at index `skip`.
+    fn current_without(&self, skip: Option<usize>) -> Option<&Medium> {
+        self.entries[..self.len]
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != skip)
+            .filter_map(|(_, entry)| entry.medium.as_ref())
+            .max_by_key(|medium| medium.priority)
+    }
+
+    /// Index of refraction of the current medium at `wavelength`, in nanometres.
+    pub fn ior(&self, wavelength: f64) -> f64 {
+        self.current()
+            .map_or(1., |medium| medium.ior.at(wavelength))
+    }
+
+    /// Absorption coefficient of the current medium. Light travelling a distance `d` through it is
+    /// dimmed by `exp(-absorption * d)`, following the Beer–Lambert law.
+    pub fn absorption(&self) -> Color {
+        self.current()
+            .map_or(Color::ZERO, |medium| medium.absorption)
+    }
+
+    /// Index of refraction outside a surface of `medium`, or `None` if the surface is hidden by a
+    /// medium of higher priority and should be passed through.
+    pub fn exterior_ior(
+        &self,
+        id: usize,
+        medium: &Medium,
+        entering: bool,
+        wavelength: f64,
+    ) -> Option<f64> {
+        let inside = if entering { None } else { self.position(id) };
+        match self.current_without(inside) {
+            Some(current) if current.priority > medium.priority => None,
+            outside => Some(outside.map_or(1., |outside| outside.ior.at(wavelength))),
+        }
+    }
+
+    /// Update the stack for a ray crossing a surface of `medium`.
+    pub fn cross(&mut self, id: usize, medium: Medium, entering: bool) {
+        if entering {
+            self.push(id, medium);
+        } else {
+            self.remove(id);
+        }
+    }
+
+    fn push(&mut self, id: usize, medium: Medium) {
+        if self.len < MAX_DEPTH {
+            self.entries[self.len] = Entry {
+                id,
+                medium: Some(medium),
+            };
+            self.len += 1;
+        }
+    }
+
+    /// Index of the innermost entry for the material `id`.
+    fn position(&self, id: usize) -> Option<usize> {
+        self.entries[..self.len].iter().rposition(|e| e.id == id)
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(i) = self.position(id) {
+            self.entries[i..self.len].rotate_left(1);
+            self.len -= 1;
+        }
+    }
+}
@@ -16,6 +16,13 @@ impl RawImageBuffer {
         }
     }
 
+    /// Gamma correct and quantize linear colors given in row-major order.
+    pub fn from_colors(width: u32, height: u32, colors: &[Color]) -> Self {
+        let mut rawbuf = Self::new(width, height);
+        colors.iter().for_each(|color| rawbuf.push_color(*color));
+        rawbuf
+    }
+
     pub fn push_rgb(&mut self, r: u8, g: u8, b: u8) {
         self.buf.push(r);
         self.buf.push(g);
@@ -43,4 +50,18 @@ impl RawImageBuffer {
             image::ExtendedColorType::Rgb8,
         )
     }
+
+    pub fn save_with_format<T>(&self, path: T, format: image::ImageFormat) -> image::ImageResult<()>
+    where
+        T: AsRef<std::path::Path>,
+    {
+        image::save_buffer_with_format(
+            path,
+            &self.buf,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgb8,
+            format,
+        )
+    }
 }
@@ -1,16 +1,50 @@
+use crate::medium::MediumStack;
+use crate::spectrum::{Wavelengths, REFERENCE_WAVELENGTH};
 use crate::Vec3;
 
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    /// Width of the cone of directions this ray stands in for, at its origin. Used to pick how
+    /// blurry a texture lookup should be.
+    pub width: f64,
+    /// How fast the cone widens, per unit of distance travelled
+    pub spread: f64,
+    /// Refractive media the ray is inside
+    pub media: MediumStack,
+    /// Wavelengths carried in the channels of colors along this ray, in spectral renders
+    pub wavelengths: Option<Wavelengths>,
+    /// Probability density of the direction, when it was scattered off a surface where lights
+    /// were also sampled directly. Light the ray finds is weighed against that, see
+    /// [`crate::light`].
+    pub scatter_pdf: Option<f64>,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            width: 0.,
+            spread: 0.,
+            media: MediumStack::default(),
+            wavelengths: None,
+            scatter_pdf: None,
+        }
     }
 
     pub fn at(&self, t: f64) -> Vec3 {
         self.origin + self.direction * t
     }
+
+    /// The wavelength to evaluate wavelength dependent properties at, in nanometres.
+    pub fn wavelength(&self) -> f64 {
+        self.wavelengths
+            .map_or(REFERENCE_WAVELENGTH, |wavelengths| wavelengths.hero())
+    }
+
+    /// Width of the ray cone at `t`, perpendicular to the ray.
+    pub fn footprint(&self, t: f64) -> f64 {
+        self.width + self.spread * t * self.direction.length()
+    }
 }
@@ -0,0 +1,453 @@
+//! Scenes stored on disk, so they can be changed without recompiling.
+//!
+//! Scene files are TOML. Every section is optional:
+//!
+//! ```toml
+//! background = { type = "gradient", bottom = [1.0, 1.0, 1.0], top = [0.5, 0.7, 1.0] }
+//! # Or a daylight sky, which also adds its sun to the lights: degrees above the horizon and from
+//! # -Z towards +X, and haziness from 2 to 10
+//! # background = { type = "sky", elevation = 30.0, azimuth = 120.0, turbidity = 3.0 }
+//! # Interactions per unit of distance, and the fraction that scatter rather than absorb light
+//! fog = { density = 0.01, albedo = [0.9, 0.9, 0.9] }
+//!
+//! # Any `CameraBuilder` setting
+//! [camera]
+//! image_width = 400
+//! position = [13.0, 2.0, 3.0]
+//! look_at = [0.0, 0.0, 0.0]
+//!
+//! # Materials are named so shapes can share them
+//! [materials.ground]
+//! type = "lambertian"
+//! albedo = [0.5, 0.5, 0.5]
+//!
+//! [[shapes]]
+//! type = "sphere"
+//! center = [0.0, -1000.0, 0.0]
+//! radius = 1000.0
+//! material = "ground"
+//!
+//! # Texture coordinates default to (0, 0), (1, 0) and (0, 1)
+//! [[shapes]]
+//! type = "triangle"
+//! vertices = [[-1.0, 0.0, -1.0], [1.0, 0.0, -1.0], [0.0, 1.0, -1.0]]
+//! uvs = [[0.0, 0.0], [1.0, 0.0], [0.5, 1.0]]
+//! material = "ground"
+//!
+//! # Lights are shorthand for shapes with a `diffuse_light` material
+//! [[lights]]
+//! type = "sphere"
+//! center = [0.0, 10.0, 0.0]
+//! radius = 2.0
+//! color = [1.0, 0.9, 0.8]
+//! intensity = 4.0
+//!
+//! # Or lights without geometry: `point`, `spot` and `sun`
+//! [[lights]]
+//! type = "spot"
+//! position = [0.0, 5.0, 0.0]
+//! direction = [0.0, -1.0, 0.0]
+//! color = [1.0, 1.0, 1.0]
+//! intensity = 50.0
+//! # Degrees from the axis to the edge of the cone, and over which it fades out
+//! angle = 30.0
+//! falloff = 5.0
+//!
+//! [[lights]]
+//! type = "sun"
+//! direction = [1.0, 2.0, 1.0]
+//! color = [1.0, 0.95, 0.9]
+//! intensity = 3.0
+//! angular_diameter = 0.53
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use crate::background::Background;
+use crate::camera::{Camera, CameraBuilder};
+use crate::light::{PointLight, SpotLight, SunLight};
+use crate::material::{DiffuseLight, Material};
+use crate::medium::Fog;
+use crate::shapes::heterogeneous_medium::HeterogeneousMedium;
+use crate::shapes::sphere::Sphere;
+use crate::shapes::triangle::Triangle;
+use crate::shapes::Shape;
+use crate::{Color, Vec2, Vec3};
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub camera: CameraBuilder,
+    pub world: Vec<Shape>,
+}
+
+impl Scene {
+    pub fn new(camera: CameraBuilder, world: Vec<Shape>) -> Self {
+        Self { camera, world }
+    }
+
+    /// Load a scene from a TOML file.
+    pub fn load<T>(path: T) -> Result<Self, SceneError>
+    where
+        T: AsRef<Path>,
+    {
+        let source = std::fs::read_to_string(path).map_err(SceneError::Io)?;
+        Scene::from_toml(&source)
+    }
+
+    /// Parse a scene from TOML source.
+    pub fn from_toml(source: &str) -> Result<Self, SceneError> {
+        let file: SceneFile = toml::from_str(source).map_err(SceneError::Parse)?;
+        file.into_scene(source)
+    }
+
+    /// Save the scene as a TOML file. Each shape gets its own material.
+    pub fn save<T>(&self, path: T) -> Result<(), SceneError>
+    where
+        T: AsRef<Path>,
+    {
+        std::fs::write(path, self.to_toml()?).map_err(SceneError::Io)
+    }
+
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        let mut materials = BTreeMap::new();
+        let shapes = self
+            .world
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| {
+                let name = format!("material-{i:04}");
+                Spanned::new(0..0, shape_file(shape, None, name, &mut materials))
+            })
+            .collect();
+
+        let file = SceneFile {
+            camera: self.camera.clone(),
+            background: None,
+            fog: None,
+            materials,
+            shapes,
+            lights: vec![],
+        };
+        toml::to_string(&file).map_err(SceneError::Serialize)
+    }
+
+    /// Build the camera, returning it alongside the world.
+    pub fn build(self) -> (Vec<Shape>, Camera) {
+        (self.world, self.camera.build())
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    /// Syntax errors, unknown fields and wrongly typed values
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    /// Values that are well formed but don't make sense
+    Invalid {
+        /// Line and column of the offending value, if known
+        location: Option<(usize, usize)>,
+        field: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{err}"),
+            SceneError::Parse(err) => write!(f, "{}", err.to_string().trim_end()),
+            SceneError::Serialize(err) => write!(f, "{err}"),
+            SceneError::Invalid {
+                location: Some((line, column)),
+                field,
+                message,
+            } => write!(f, "line {line}, column {column}: `{field}`: {message}"),
+            SceneError::Invalid {
+                location: None,
+                field,
+                message,
+            } => write!(f, "`{field}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Parse(err) => Some(err),
+            SceneError::Serialize(err) => Some(err),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    camera: CameraBuilder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    background: Option<Background>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fog: Option<Fog>,
+    #[serde(default)]
+    materials: BTreeMap<String, Arc<dyn Material>>,
+    #[serde(default)]
+    shapes: Vec<Spanned<ShapeFile>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    lights: Vec<LightFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ShapeFile {
+    Sphere {
+        center: Vec3,
+        radius: f64,
+        material: String,
+    },
+    Triangle {
+        vertices: [Vec3; 3],
+        #[serde(default)]
+        uvs: Option<[Vec2; 3]>,
+        material: String,
+    },
+    HeterogeneousMedium(HeterogeneousMedium),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightFile {
+    Sphere {
+        center: Vec3,
+        radius: f64,
+        color: Color,
+        #[serde(default = "default_intensity")]
+        intensity: f64,
+    },
+    Point(PointLight),
+    Spot(SpotLight),
+    Sun(SunLight),
+}
+
+fn default_intensity() -> f64 {
+    1.
+}
+
+impl SceneFile {
+    fn into_scene(self, source: &str) -> Result<Scene, SceneError> {
+        let mut camera = self.camera;
+        let mut world = Vec::with_capacity(self.shapes.len() + self.lights.len());
+
+        for (i, shape) in self.shapes.into_iter().enumerate() {
+            let span = shape.span();
+            let material = |name: &str| {
+                self.materials.get(name).cloned().ok_or_else(|| {
+                    let known = self
+                        .materials
+                        .keys()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>();
+                    SceneError::Invalid {
+                        location: Some(line_column(source, span.start)),
+                        field: format!("shapes[{i}].material"),
+                        message: format!(
+                            "unknown material `{}`, expected one of: {}",
+                            name,
+                            known.join(", ")
+                        ),
+                    }
+                })
+            };
+
+            match shape.into_inner() {
+                ShapeFile::Sphere {
+                    center,
+                    radius,
+                    material: name,
+                } => {
+                    validate_radius(&format!("shapes[{i}].radius"), radius)?;
+                    world.push(Shape::Sphere(Sphere::new(center, radius, material(&name)?)));
+                }
+                ShapeFile::Triangle {
+                    vertices,
+                    uvs,
+                    material: name,
+                } => {
+                    let mut triangle = Triangle::new(vertices, material(&name)?);
+                    if let Some(uvs) = uvs {
+                        triangle = triangle.uvs(uvs);
+                    }
+                    world.push(Shape::Triangle(triangle));
+                }
+                ShapeFile::HeterogeneousMedium(medium) => {
+                    let volume = &medium.volume;
+                    if volume.min.cmpge(volume.max).any() {
+                        return Err(SceneError::Invalid {
+                            location: Some(line_column(source, span.start)),
+                            field: format!("shapes[{i}].max"),
+                            message: "must be greater than `min` along every axis".to_string(),
+                        });
+                    }
+                    world.push(Shape::HeterogeneousMedium(medium));
+                }
+            }
+        }
+
+        for (i, light) in self.lights.into_iter().enumerate() {
+            match light {
+                LightFile::Sphere {
+                    center,
+                    radius,
+                    color,
+                    intensity,
+                } => {
+                    validate_radius(&format!("lights[{i}].radius"), radius)?;
+                    let emit = color * intensity;
+                    world.push(Shape::Sphere(Sphere::new(
+                        center,
+                        radius,
+                        Arc::new(DiffuseLight::new(emit)),
+                    )));
+                }
+                LightFile::Point(point) => camera = camera.light(point),
+                LightFile::Spot(spot) => {
+                    validate_direction(&format!("lights[{i}].direction"), spot.direction)?;
+                    if !(spot.angle > 0. && spot.angle <= 180.) {
+                        return Err(invalid(
+                            format!("lights[{i}].angle"),
+                            "must be greater than 0 and at most 180 degrees",
+                        ));
+                    }
+                    if !(0. ..=spot.angle).contains(&spot.falloff) {
+                        return Err(invalid(
+                            format!("lights[{i}].falloff"),
+                            "must be between 0 and `angle`",
+                        ));
+                    }
+                    camera = camera.light(spot);
+                }
+                LightFile::Sun(sun) => {
+                    validate_direction(&format!("lights[{i}].direction"), sun.direction)?;
+                    if !(0. ..180.).contains(&sun.angular_diameter) {
+                        return Err(invalid(
+                            format!("lights[{i}].angular_diameter"),
+                            "must be at least 0 and less than 180 degrees",
+                        ));
+                    }
+                    camera = camera.light(sun);
+                }
+            }
+        }
+
+        if let Some(background) = self.background {
+            if let Background::Sky(sky) = &background {
+                if !(0. ..=90.).contains(&sky.elevation) {
+                    return Err(invalid(
+                        "background.elevation".to_string(),
+                        "must be between 0 and 90 degrees",
+                    ));
+                }
+                if !(2. ..=10.).contains(&sky.turbidity) {
+                    return Err(invalid(
+                        "background.turbidity".to_string(),
+                        "must be between 2 and 10",
+                    ));
+                }
+            }
+            camera = camera.background(background);
+        }
+        if let Some(fog) = self.fog {
+            camera = camera.fog(fog);
+        }
+
+        Ok(Scene { camera, world })
+    }
+}
+
+/// The file form of `shape`, adding its material to `materials` as `name`. Volumes are saved as
+/// their boundary with a `volume` material.
+fn shape_file(
+    shape: &Shape,
+    material: Option<Arc<dyn Material>>,
+    name: String,
+    materials: &mut BTreeMap<String, Arc<dyn Material>>,
+) -> ShapeFile {
+    match shape {
+        Shape::Sphere(sphere) => {
+            materials.insert(
+                name.clone(),
+                material.unwrap_or_else(|| sphere.material.clone()),
+            );
+            ShapeFile::Sphere {
+                center: sphere.center,
+                radius: sphere.radius,
+                material: name,
+            }
+        }
+        Shape::Triangle(triangle) => {
+            materials.insert(
+                name.clone(),
+                material.unwrap_or_else(|| triangle.material.clone()),
+            );
+            ShapeFile::Triangle {
+                vertices: triangle.vertices,
+                uvs: Some(triangle.uvs),
+                material: name,
+            }
+        }
+        // Boxes only ever hold their own volume in scene files
+        Shape::HeterogeneousMedium(medium) => ShapeFile::HeterogeneousMedium(medium.clone()),
+        Shape::ConstantMedium(medium) => shape_file(
+            &medium.boundary,
+            Some(material.unwrap_or_else(|| Arc::new(medium.volume.clone()))),
+            name,
+            materials,
+        ),
+    }
+}
+
+fn invalid(field: String, message: impl Into<String>) -> SceneError {
+    SceneError::Invalid {
+        location: None,
+        field,
+        message: message.into(),
+    }
+}
+
+fn validate_radius(field: &str, radius: f64) -> Result<(), SceneError> {
+    // Hollow spheres are nested media rather than negative radii, so these are mistakes
+    if !(radius > 0. && radius.is_finite()) {
+        return Err(invalid(
+            field.to_string(),
+            "radius must be positive and finite",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_direction(field: &str, direction: Vec3) -> Result<(), SceneError> {
+    if direction == Vec3::ZERO || !direction.is_finite() {
+        return Err(invalid(
+            field.to_string(),
+            "direction must be finite and non-zero",
+        ));
+    }
+    Ok(())
+}
+
+/// 1-based line and column of a byte offset into `source`.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
@@ -0,0 +1,41 @@
+use std::ops::Range;
+
+use crate::hit_record::{HitRecord, Hittable};
+use crate::material::Volume;
+use crate::ray::Ray;
+use crate::shapes::Shape;
+use crate::Color;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Fills a closed shape with smoke or mist of constant density, ignoring the shape's own
+/// material.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConstantMedium {
+    pub boundary: Box<Shape>,
+    pub volume: Volume,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Shape, density: f64, albedo: Color) -> Self {
+        Self {
+            boundary: Box::new(boundary),
+            volume: Volume::new(density, albedo),
+        }
+    }
+
+    /// Henyey–Greenstein asymmetry of scattering inside, 0 for isotropic.
+    pub fn anisotropy(mut self, anisotropy: f64) -> Self {
+        self.volume = self.volume.anisotropy(anisotropy);
+        self
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
+        let mut hit_record = self.boundary.hit(ray, interval)?;
+        hit_record.material = &self.volume;
+        Some(hit_record)
+    }
+}
@@ -0,0 +1,76 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::density::DensityField;
+use crate::hit_record::{HitRecord, Hittable};
+use crate::material::HeterogeneousVolume;
+use crate::ray::Ray;
+use crate::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An axis-aligned box filled with smoke or cloud whose density comes from a density field, such
+/// as a voxel grid from a simulation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct HeterogeneousMedium {
+    /// The volume inside, whose box is also the boundary
+    pub volume: HeterogeneousVolume,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(field: Arc<dyn DensityField>, min: Vec3, max: Vec3, density: f64) -> Self {
+        Self {
+            volume: HeterogeneousVolume::new(field, min, max, density),
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
+        let (min, max) = (self.volume.min, self.volume.max);
+
+        // Where the ray crosses each pair of planes bounding the box
+        let inverse = ray.direction.recip();
+        let t0 = (min - ray.origin) * inverse;
+        let t1 = (max - ray.origin) * inverse;
+        let near = t0.min(t1).max_element();
+        let far = t0.max(t1).min_element();
+        if near > far {
+            return None;
+        }
+        let t = if interval.contains(&near) {
+            near
+        } else if interval.contains(&far) {
+            far
+        } else {
+            return None;
+        };
+
+        // The face hit is the one the point is furthest towards
+        let point = ray.at(t);
+        let local = (point - 0.5 * (min + max)) / (max - min);
+        let distance = local.abs();
+        let axis = if distance.x >= distance.y.max(distance.z) {
+            0
+        } else if distance.y >= distance.z {
+            1
+        } else {
+            2
+        };
+        let mut outward_normal = Vec3::ZERO;
+        outward_normal[axis] = local[axis].signum();
+
+        let mut hit_record = HitRecord {
+            point,
+            t,
+            material: &self.volume,
+            ..Default::default()
+        };
+        hit_record.set_face_normal(ray, outward_normal);
+        hit_record.set_tangents();
+        hit_record.set_footprint(ray);
+        Some(hit_record)
+    }
+}
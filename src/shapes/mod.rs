@@ -1,20 +1,75 @@
+pub mod constant_medium;
+pub mod heterogeneous_medium;
 pub mod sphere;
+pub mod triangle;
 
 use std::ops::Range;
 
+use rand::RngCore;
+
 use crate::hit_record::{HitRecord, Hittable};
 use crate::ray::Ray;
+use crate::shapes::constant_medium::ConstantMedium;
+use crate::shapes::heterogeneous_medium::HeterogeneousMedium;
 use crate::shapes::sphere::Sphere;
+use crate::shapes::triangle::Triangle;
+use crate::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[non_exhaustive]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum Shape {
     Sphere(Sphere),
+    Triangle(Triangle),
+    ConstantMedium(ConstantMedium),
+    HeterogeneousMedium(HeterogeneousMedium),
 }
 
 impl Hittable for Shape {
-    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
         match self {
             Shape::Sphere(sphere) => sphere.hit(ray, interval),
+            Shape::Triangle(triangle) => triangle.hit(ray, interval),
+            Shape::ConstantMedium(medium) => medium.hit(ray, interval),
+            Shape::HeterogeneousMedium(medium) => medium.hit(ray, interval),
+        }
+    }
+
+    fn lights(&self) -> Vec<&Shape> {
+        let emissive = match self {
+            Shape::Sphere(sphere) => sphere.material.is_emissive(),
+            Shape::Triangle(triangle) => triangle.material.is_emissive(),
+            Shape::ConstantMedium(_) | Shape::HeterogeneousMedium(_) => false,
+        };
+        if emissive {
+            vec![self]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Shape {
+    /// Sample a direction from `origin` towards the shape, for sampling it as a light. `None` for
+    /// media, which can't be.
+    pub fn sample_direction(&self, origin: Vec3, rng: &mut dyn RngCore) -> Option<Vec3> {
+        match self {
+            Shape::Sphere(sphere) => Some(sphere.sample_direction(origin, rng)),
+            Shape::Triangle(triangle) => Some(triangle.sample_direction(origin, rng)),
+            Shape::ConstantMedium(_) | Shape::HeterogeneousMedium(_) => None,
+        }
+    }
+
+    /// Probability density of `sample_direction` choosing `direction` from `origin`, per unit
+    /// solid angle.
+    pub fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        match self {
+            Shape::Sphere(sphere) => sphere.pdf(origin, direction),
+            Shape::Triangle(triangle) => triangle.pdf(origin, direction),
+            Shape::ConstantMedium(_) | Shape::HeterogeneousMedium(_) => 0.,
         }
     }
 }
@@ -1,28 +1,94 @@
+use std::f64::consts::PI;
 use std::ops::Range;
+use std::sync::Arc;
+
+use rand::RngCore;
 
 use crate::hit_record::{FaceSide, HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::Vec3;
+use crate::vectors::{cone_pdf, random_in_cone, random_unit_vector};
+use crate::{Vec2, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f64,
-    pub material: Material,
+    pub material: Arc<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Vec3, radius: f64, material: Material) -> Self {
+    pub fn new(center: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
         Self {
             center,
             radius,
             material,
         }
     }
+
+    /// Texture coordinates of a point on the unit sphere. `u` is the angle around the Y axis
+    /// starting from -X, `v` runs from the bottom (-Y) to the top (+Y).
+    fn uv(point: Vec3) -> Vec2 {
+        let theta = (-point.y).clamp(-1., 1.).acos();
+        let phi = (-point.z).atan2(point.x) + PI;
+        Vec2::new(phi / (2. * PI), theta / PI)
+    }
+
+    /// Partial derivatives of the surface point with respect to `u` and `v`, at `point` on the
+    /// unit sphere. `dpdv` is zero at the poles.
+    fn dpduv(&self, point: Vec3) -> (Vec3, Vec3) {
+        let radius = self.radius.abs();
+        let dpdu = 2. * PI * radius * Vec3::new(point.z, 0., -point.x);
+        let rho = point.x.hypot(point.z);
+        let dpdv = if rho > 1e-9 {
+            PI * radius * Vec3::new(-point.x * point.y / rho, rho, -point.y * point.z / rho)
+        } else {
+            Vec3::ZERO
+        };
+        (dpdu, dpdv)
+    }
+
+    /// Sample a direction from `origin` towards the sphere, for sampling it as a light. From
+    /// outside, directions are uniform over the cone the sphere covers; from inside, points are
+    /// uniform over its surface.
+    pub fn sample_direction(&self, origin: Vec3, rng: &mut dyn RngCore) -> Vec3 {
+        let radius = self.radius.abs();
+        let to_center = self.center - origin;
+        let distance_squared = to_center.length_squared();
+        if distance_squared <= radius * radius {
+            let point = self.center + radius * random_unit_vector(rng);
+            return point - origin;
+        }
+
+        let cos_theta_max = (1. - radius * radius / distance_squared).sqrt();
+        random_in_cone(to_center.normalize(), cos_theta_max, rng)
+    }
+
+    /// Probability density of `sample_direction` choosing `direction` from `origin`, per unit
+    /// solid angle.
+    pub fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        let radius = self.radius.abs();
+        let ray = Ray::new(origin, direction);
+        let Some(hit_record) = self.hit(&ray, 0.001..f64::INFINITY) else {
+            return 0.;
+        };
+
+        let distance_squared = (self.center - origin).length_squared();
+        if distance_squared <= radius * radius {
+            let to_point = hit_record.point - origin;
+            let cosine = hit_record.normal.dot(direction.normalize()).abs();
+            to_point.length_squared() / (cosine * 4. * PI * radius * radius)
+        } else {
+            cone_pdf((1. - radius * radius / distance_squared).sqrt())
+        }
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
         let oc = self.center - ray.origin;
         let a = ray.direction.length_squared();
         let half_b = oc.dot(ray.direction);
@@ -48,16 +114,28 @@ impl Hittable for Sphere {
 
         let point = ray.at(root);
         let outward_normal = (point - self.center) / self.radius;
+        let unit_point = outward_normal * self.radius.signum();
+        let (dpdu, dpdv) = self.dpduv(unit_point);
 
         let mut hit_record = HitRecord {
             point,
             normal: outward_normal,
+            geometric_normal: outward_normal,
             t: root,
+            uv: Sphere::uv(unit_point),
+            dpdu,
+            dpdv,
+            tangent: Vec3::ZERO,
+            bitangent: Vec3::ZERO,
+            footprint: 0.,
+            exterior_ior: 1.,
             face_side: FaceSide::Front,
-            material: self.material.clone(),
+            material: self.material.as_ref(),
         };
 
         hit_record.set_face_normal(ray, outward_normal);
+        hit_record.set_tangents();
+        hit_record.set_footprint(ray);
 
         Some(hit_record)
     }
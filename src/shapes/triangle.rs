@@ -0,0 +1,142 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+
+use crate::hit_record::{FaceSide, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::{Vec2, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A single triangle. The front face is the one the vertices wind counter-clockwise around.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    /// Texture coordinates of each vertex
+    #[cfg_attr(feature = "serde", serde(default = "Triangle::default_uvs"))]
+    pub uvs: [Vec2; 3],
+    pub material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vec3; 3], material: Arc<dyn Material>) -> Self {
+        Self {
+            vertices,
+            uvs: Triangle::default_uvs(),
+            material,
+        }
+    }
+
+    pub fn uvs(mut self, uvs: [Vec2; 3]) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    fn default_uvs() -> [Vec2; 3] {
+        [Vec2::ZERO, Vec2::X, Vec2::Y]
+    }
+
+    /// Partial derivatives of the surface point with respect to `u` and `v`. Zero if the texture
+    /// coordinates are degenerate.
+    fn dpduv(&self, edge1: Vec3, edge2: Vec3) -> (Vec3, Vec3) {
+        let duv1 = self.uvs[1] - self.uvs[0];
+        let duv2 = self.uvs[2] - self.uvs[0];
+        let determinant = duv1.perp_dot(duv2);
+        if determinant.abs() < 1e-12 {
+            return (Vec3::ZERO, Vec3::ZERO);
+        }
+        let dpdu = (duv2.y * edge1 - duv1.y * edge2) / determinant;
+        let dpdv = (duv1.x * edge2 - duv2.x * edge1) / determinant;
+        (dpdu, dpdv)
+    }
+
+    fn area(&self) -> f64 {
+        let [v0, v1, v2] = self.vertices;
+        0.5 * (v1 - v0).cross(v2 - v0).length()
+    }
+
+    /// Sample a direction from `origin` to a point uniformly distributed over the triangle, for
+    /// sampling it as a light.
+    pub fn sample_direction(&self, origin: Vec3, rng: &mut dyn RngCore) -> Vec3 {
+        let [v0, v1, v2] = self.vertices;
+        let root = rng.gen::<f64>().sqrt();
+        let b1 = root * (1. - rng.gen::<f64>());
+        let b2 = root - b1;
+        let point = (1. - b1 - b2) * v0 + b1 * v1 + b2 * v2;
+        point - origin
+    }
+
+    /// Probability density of `sample_direction` choosing `direction` from `origin`, per unit
+    /// solid angle.
+    pub fn pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let Some(hit_record) = self.hit(&ray, 0.001..f64::INFINITY) else {
+            return 0.;
+        };
+        // Convert from area to solid angle
+        let to_point = hit_record.point - origin;
+        let cosine = hit_record.normal.dot(direction.normalize()).abs();
+        to_point.length_squared() / (cosine * self.area())
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord<'_>> {
+        // Möller–Trumbore
+        let [v0, v1, v2] = self.vertices;
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = ray.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+
+        // Parallel to the triangle
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        let to_origin = ray.origin - v0;
+        let b1 = to_origin.dot(p) / determinant;
+        if !(0. ..=1.).contains(&b1) {
+            return None;
+        }
+        let q = to_origin.cross(edge1);
+        let b2 = ray.direction.dot(q) / determinant;
+        if b2 < 0. || b1 + b2 > 1. {
+            return None;
+        }
+
+        let t = edge2.dot(q) / determinant;
+        if !interval.contains(&t) {
+            return None;
+        }
+
+        let outward_normal = edge1.cross(edge2).normalize();
+        let uv = (1. - b1 - b2) * self.uvs[0] + b1 * self.uvs[1] + b2 * self.uvs[2];
+        let (dpdu, dpdv) = self.dpduv(edge1, edge2);
+
+        let mut hit_record = HitRecord {
+            point: ray.at(t),
+            normal: outward_normal,
+            geometric_normal: outward_normal,
+            t,
+            uv,
+            dpdu,
+            dpdv,
+            tangent: Vec3::ZERO,
+            bitangent: Vec3::ZERO,
+            footprint: 0.,
+            exterior_ior: 1.,
+            face_side: FaceSide::Front,
+            material: self.material.as_ref(),
+        };
+
+        hit_record.set_face_normal(ray, outward_normal);
+        hit_record.set_tangents();
+        hit_record.set_footprint(ray);
+
+        Some(hit_record)
+    }
+}
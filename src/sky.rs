@@ -0,0 +1,185 @@
+//! Daylight sky from the analytic model of Preetham, Shirley and Smits (1999).
+//!
+//! The sky's luminance and chromaticity are fitted to the position of the sun and the turbidity of
+//! the air, with Perez et al.'s formula for how they vary across the sky. The sun itself is a
+//! [`SunLight`] whose color is sunlight after Rayleigh and aerosol extinction along its path
+//! through the atmosphere, so it turns orange and dim as it sets.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::light::SunLight;
+use crate::spectrum::{spectrum_to_rgb, xyz_to_rgb};
+use crate::{Color, Vec3};
+
+/// Illuminance from the sun above the atmosphere, in kilolux. The sky's luminance is in kilocandela
+/// per square meter, so they add up.
+const SOLAR_ILLUMINANCE: f64 = 128.;
+
+/// Kilocandela per square meter in a unit of radiance, chosen so that a white surface in the
+/// midday sun comes out about white.
+const EXPOSURE: f64 = 40.;
+
+/// Luminance of a linear sRGB color
+const LUMINANCE: Color = Color::new(0.2126, 0.7152, 0.0722);
+
+/// Angle the sun covers, in degrees
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+
+/// Sky lit by the sun at `elevation` and `azimuth`, which the camera also adds as a light.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Sky {
+    /// Angle of the sun above the horizon, in degrees
+    pub elevation: f64,
+    /// Compass direction of the sun, in degrees: 0 is towards -Z and 90 towards +X
+    pub azimuth: f64,
+    /// Haziness of the air, from 2 for a very clear day to 10 for a hazy one
+    #[cfg_attr(feature = "serde", serde(default = "default_turbidity"))]
+    pub turbidity: f64,
+    /// Scales both the sky and the sun
+    #[cfg_attr(feature = "serde", serde(default = "one"))]
+    pub intensity: f64,
+}
+
+impl Sky {
+    pub fn new(elevation: f64, azimuth: f64) -> Self {
+        Self {
+            elevation,
+            azimuth,
+            turbidity: default_turbidity(),
+            intensity: 1.,
+        }
+    }
+
+    pub fn turbidity(mut self, turbidity: f64) -> Self {
+        self.turbidity = turbidity;
+        self
+    }
+
+    pub fn intensity(mut self, intensity: f64) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Unit vector towards the sun.
+    pub fn sun_direction(&self) -> Vec3 {
+        let (sin_elevation, cos_elevation) = self.elevation.to_radians().sin_cos();
+        let (sin_azimuth, cos_azimuth) = self.azimuth.to_radians().sin_cos();
+        Vec3::new(
+            cos_elevation * sin_azimuth,
+            sin_elevation,
+            -cos_elevation * cos_azimuth,
+        )
+    }
+
+    /// The sun's disc, as seen through the atmosphere.
+    pub fn sun(&self) -> SunLight {
+        let theta_sun = self.theta_sun();
+        // Kasten's relative optical air mass, which stays finite at the horizon
+        let air_mass =
+            1. / (theta_sun.cos() + 0.15 * (93.885 - theta_sun.to_degrees()).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |lambda: f64| {
+            let micrometers = lambda * 1e-3;
+            let rayleigh = 0.008735 * micrometers.powf(-4.08);
+            let aerosol = beta * micrometers.powf(-1.3);
+            (-(rayleigh + aerosol) * air_mass).exp()
+        };
+        // Normalized so the sun above the atmosphere has a luminance of one
+        let outside = spectrum_to_rgb(sunlight);
+        let color = spectrum_to_rgb(|lambda| sunlight(lambda) * transmittance(lambda))
+            / outside.dot(LUMINANCE);
+        SunLight::new(self.sun_direction(), color.max(Color::ZERO))
+            .intensity(self.intensity * SOLAR_ILLUMINANCE / EXPOSURE)
+            .angular_diameter(SUN_ANGULAR_DIAMETER)
+    }
+
+    /// Light from the sky along the unit `direction`, leaving out the sun's disc. Below the
+    /// horizon the sky keeps its color at the horizon.
+    pub fn radiance(&self, direction: Vec3) -> Color {
+        let t = self.turbidity;
+        let theta_sun = self.theta_sun();
+        let cos_theta = direction.y.max(f64::EPSILON);
+        let cos_gamma = direction.dot(self.sun_direction()).clamp(-1., 1.);
+        let gamma = cos_gamma.acos();
+
+        let perez = |[a, b, c, d, e]: [f64; 5]| {
+            let distribution = |cos_theta: f64, gamma: f64| {
+                (1. + a * (b / cos_theta).exp())
+                    * (1. + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+            };
+            distribution(cos_theta, gamma) / distribution(1., theta_sun)
+        };
+
+        // Luminance and chromaticity at the zenith
+        let chi = (4. / 9. - t / 120.) * (std::f64::consts::PI - 2. * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.];
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let [t2, t1, t0] = m.map(|row| row.iter().zip(thetas).map(|(m, x)| m * x).sum::<f64>());
+            t * t * t2 + t * t1 + t0
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let luminance = zenith_luminance
+            * perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]);
+        let x = zenith_x
+            * perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]);
+        let y = zenith_y
+            * perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]);
+
+        let xyz = Color::new(x * luminance / y, luminance, (1. - x - y) * luminance / y);
+        xyz_to_rgb(xyz).max(Color::ZERO) * self.intensity / EXPOSURE
+    }
+
+    /// Angle of the sun from the zenith, in radians. The model only holds between the horizon and
+    /// the zenith, so a sun beyond them colors the sky and itself as if it were at the nearest.
+    fn theta_sun(&self) -> f64 {
+        (90. - self.elevation.clamp(0., 90.)).to_radians()
+    }
+}
+
+/// Spectrum of sunlight above the atmosphere, as a black body at the temperature of the sun's
+/// surface, relative to its value at 560 nm.
+fn sunlight(lambda: f64) -> f64 {
+    let planck = |lambda: f64| 1. / (lambda.powi(5) * ((1.4388e7 / (lambda * 5778.)).exp() - 1.));
+    planck(lambda) / planck(560.)
+}
+
+fn default_turbidity() -> f64 {
+    3.
+}
+
+#[cfg(feature = "serde")]
+fn one() -> f64 {
+    1.
+}
@@ -0,0 +1,196 @@
+//! Spectral rendering, for effects such as dispersion that depend on the wavelength of light.
+//!
+//! In spectral mode each camera sample traces a few wavelengths at once (hero wavelength sampling,
+//! Wilkie et al. 2014), carried in the three channels of a [`Color`]. Materials, textures and
+//! backgrounds still work in RGB: their colors are upsampled to spectra at the sampled wavelengths
+//! (Smits 1999), and the radiance that reaches the camera is converted back to RGB through the CIE
+//! color matching functions.
+
+use std::sync::OnceLock;
+
+use crate::Color;
+
+/// Shortest wavelength sampled, in nanometres
+pub const MIN_WAVELENGTH: f64 = 380.;
+/// Longest wavelength sampled, in nanometres
+pub const MAX_WAVELENGTH: f64 = 720.;
+/// The sodium D line, where indices of refraction are usually quoted. Used for rays that don't
+/// carry wavelengths.
+pub const REFERENCE_WAVELENGTH: f64 = 589.3;
+
+const RANGE: f64 = MAX_WAVELENGTH - MIN_WAVELENGTH;
+
+/// Wavelengths traced by one camera sample, in nanometres. The first is the hero wavelength, and
+/// the others are spaced evenly after it, wrapping around the visible range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wavelengths {
+    pub lambda: Color,
+    /// Set once only the hero wavelength is still being traced
+    terminated: bool,
+}
+
+impl Wavelengths {
+    /// Pick wavelengths from `u` in `[0, 1)`.
+    pub fn sample(u: f64) -> Self {
+        let hero = MIN_WAVELENGTH + u * RANGE;
+        let rotate = |i: f64| MIN_WAVELENGTH + (hero - MIN_WAVELENGTH + i * RANGE / 3.) % RANGE;
+        Self {
+            lambda: Color::new(hero, rotate(1.), rotate(2.)),
+            terminated: false,
+        }
+    }
+
+    /// The wavelength that decides the path where wavelengths would go different ways.
+    pub fn hero(&self) -> f64 {
+        self.lambda.x
+    }
+
+    /// Stop tracing all but the hero wavelength, for when the path depends on wavelength, like
+    /// refraction through a dispersive material. Returns the weight to apply to the path.
+    pub fn terminate_secondary(&mut self) -> Color {
+        if self.terminated {
+            Color::ONE
+        } else {
+            self.terminated = true;
+            Color::new(3., 0., 0.)
+        }
+    }
+
+    /// Values of the spectrum of an RGB color at these wavelengths.
+    pub fn upsample(&self, rgb: Color) -> Color {
+        let basis = SmitsBasis::new(rgb);
+        Color::new(
+            basis.value(self.lambda.x),
+            basis.value(self.lambda.y),
+            basis.value(self.lambda.z),
+        )
+    }
+
+    /// Convert radiance sampled at these wavelengths to linear RGB.
+    pub fn to_rgb(&self, radiance: Color) -> Color {
+        let xyz = (0..3)
+            .map(|i| radiance[i] * color_matching(self.lambda[i]))
+            .sum::<Color>()
+            * RANGE
+            / 3.;
+        xyz_to_rgb(xyz) * white_balance()
+    }
+}
+
+/// Linear RGB of light with the spectrum `value`, a function of the wavelength in nanometers, so
+/// that a spectrum of ones comes out white.
+pub fn spectrum_to_rgb(value: impl Fn(f64) -> f64) -> Color {
+    let steps = RANGE as u32;
+    let xyz: Color = (0..steps)
+        .map(|i| {
+            let lambda = MIN_WAVELENGTH + i as f64 + 0.5;
+            value(lambda) * color_matching(lambda)
+        })
+        .sum();
+    xyz_to_rgb(xyz) * white_balance()
+}
+
+/// CIE 1931 color matching functions, from the multi-lobe fit by Wyman, Sloan and Shirley (2013).
+fn color_matching(lambda: f64) -> Color {
+    let lobe = |mu: f64, below: f64, above: f64| {
+        let sigma = if lambda < mu { below } else { above };
+        (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+    };
+    Color::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+/// CIE XYZ to linear sRGB.
+pub(crate) fn xyz_to_rgb(xyz: Color) -> Color {
+    Color::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// Scale that makes the spectrum of white come out white, so spectral renders match RGB ones.
+/// This also normalizes the color matching functions.
+fn white_balance() -> Color {
+    static WHITE_BALANCE: OnceLock<Color> = OnceLock::new();
+    *WHITE_BALANCE.get_or_init(|| {
+        let white = SmitsBasis::new(Color::ONE);
+        let steps = RANGE as u32;
+        let xyz: Color = (0..steps)
+            .map(|i| {
+                let lambda = MIN_WAVELENGTH + i as f64 + 0.5;
+                white.value(lambda) * color_matching(lambda)
+            })
+            .sum();
+        1. / xyz_to_rgb(xyz)
+    })
+}
+
+/// Smits' reflectance spectra, in 10 bins from 380 to 720 nm
+const WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// The spectrum of an RGB color, as a sum of white and the smoothest primary and secondary
+/// spectra (Smits 1999).
+struct SmitsBasis {
+    terms: [(f64, &'static [f64; 10]); 3],
+}
+
+impl SmitsBasis {
+    fn new(rgb: Color) -> Self {
+        let Color { x: r, y: g, z: b } = rgb;
+        let terms = if r <= g && r <= b {
+            if g <= b {
+                [(r, &WHITE), (g - r, &CYAN), (b - g, &BLUE)]
+            } else {
+                [(r, &WHITE), (b - r, &CYAN), (g - b, &GREEN)]
+            }
+        } else if g <= r && g <= b {
+            if r <= b {
+                [(g, &WHITE), (r - g, &MAGENTA), (b - r, &BLUE)]
+            } else {
+                [(g, &WHITE), (b - g, &MAGENTA), (r - b, &RED)]
+            }
+        } else if r <= g {
+            [(b, &WHITE), (r - b, &YELLOW), (g - r, &GREEN)]
+        } else {
+            [(b, &WHITE), (g - b, &YELLOW), (r - g, &RED)]
+        };
+        Self { terms }
+    }
+
+    fn value(&self, lambda: f64) -> f64 {
+        // Interpolate between bin centres
+        let bins = WHITE.len();
+        let x = ((lambda - MIN_WAVELENGTH) / RANGE * bins as f64 - 0.5).clamp(0., bins as f64 - 1.);
+        let i = (x as usize).min(bins - 2);
+        let t = x - i as f64;
+        self.terms
+            .iter()
+            .map(|(weight, spectrum)| weight * (spectrum[i] * (1. - t) + spectrum[i + 1] * t))
+            .sum()
+    }
+}
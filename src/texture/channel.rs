@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{Texture, TextureCoords};
+use crate::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// One channel of another texture, for maps that pack several parameters into one image, such as
+/// glTF's metallic-roughness textures.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Channel {
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_texture"))]
+    pub texture: Arc<dyn Texture>,
+    pub channel: ColorChannel,
+}
+
+impl Channel {
+    pub fn new(texture: Arc<dyn Texture>, channel: ColorChannel) -> Self {
+        Self { texture, channel }
+    }
+
+    fn pick(&self, color: Color) -> Color {
+        Color::splat(match self.channel {
+            ColorChannel::Red => color.x,
+            ColorChannel::Green => color.y,
+            ColorChannel::Blue => color.z,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "channel"))]
+impl Texture for Channel {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        self.pick(self.texture.value(coords))
+    }
+
+    fn constant(&self) -> Option<Color> {
+        self.texture.constant().map(|color| self.pick(color))
+    }
+}
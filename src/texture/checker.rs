@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{SolidColor, Texture, TextureCoords};
+use crate::{Color, Vec3};
+
+/// Checkerboard of cubes in world space, so it shows up on any shape without texture coordinates.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Checker {
+    /// Side length of each cube
+    pub scale: f64,
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_texture"))]
+    pub even: Arc<dyn Texture>,
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_texture"))]
+    pub odd: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self { scale, even, odd }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "checker"))]
+impl Texture for Checker {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let point = coords.point / self.scale;
+        // Only filter across the surface. A ground plane lying on a cell boundary would otherwise
+        // average to grey.
+        let width = coords.footprint / self.scale
+            * (Vec3::ONE - coords.normal * coords.normal)
+                .max(Vec3::ZERO)
+                .map(f64::sqrt);
+        let even = square_wave_average(point.x, width.x)
+            * square_wave_average(point.y, width.y)
+            * square_wave_average(point.z, width.z);
+        blend(even, &self.even, &self.odd, coords)
+    }
+}
+
+/// Checkerboard in texture space, with `columns` by `rows` squares over the whole surface.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct UvChecker {
+    pub columns: u32,
+    pub rows: u32,
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_texture"))]
+    pub even: Arc<dyn Texture>,
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_texture"))]
+    pub odd: Arc<dyn Texture>,
+}
+
+impl UvChecker {
+    pub fn new(columns: u32, rows: u32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            columns,
+            rows,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(columns: u32, rows: u32, even: Color, odd: Color) -> Self {
+        Self::new(
+            columns,
+            rows,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "uv_checker"))]
+impl Texture for UvChecker {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let even = square_wave_average(
+            coords.uv.x * self.columns as f64,
+            coords.uv_footprint.x * self.columns as f64,
+        ) * square_wave_average(
+            coords.uv.y * self.rows as f64,
+            coords.uv_footprint.y * self.rows as f64,
+        );
+        blend(even, &self.even, &self.odd, coords)
+    }
+}
+
+/// Average over `x - width / 2` to `x + width / 2` of a wave that is 1 on even cells and -1 on
+/// odd ones. Averaging each axis like this box filters the checkerboard exactly.
+fn square_wave_average(x: f64, width: f64) -> f64 {
+    if width < 1e-6 {
+        return if x.floor().rem_euclid(2.) == 0. {
+            1.
+        } else {
+            -1.
+        };
+    }
+
+    // Integral of the wave from 0, a triangle wave
+    let integral = |x: f64| 1. - (x.rem_euclid(2.) - 1.).abs();
+    (integral(x + width / 2.) - integral(x - width / 2.)) / width
+}
+
+/// Mix the even and odd textures, from all odd at `even = -1` to all even at `even = 1`.
+fn blend(
+    even: f64,
+    even_texture: &Arc<dyn Texture>,
+    odd_texture: &Arc<dyn Texture>,
+    coords: &TextureCoords,
+) -> Color {
+    let weight = (0.5 + 0.5 * even).clamp(0., 1.);
+    if weight == 1. {
+        even_texture.value(coords)
+    } else if weight == 0. {
+        odd_texture.value(coords)
+    } else {
+        weight * even_texture.value(coords) + (1. - weight) * odd_texture.value(coords)
+    }
+}
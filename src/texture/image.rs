@@ -0,0 +1,311 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use image::DynamicImage;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{Texture, TextureCoords};
+use crate::{Color, Vec2};
+
+/// What to do with texture coordinates outside `0..1`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Wrap {
+    /// Tile the image
+    #[default]
+    Repeat,
+    /// Stretch the edge pixels
+    Clamp,
+    /// Tile the image, flipping every other copy so the edges line up
+    Mirror,
+}
+
+impl Wrap {
+    fn apply(self, i: i64, size: u32) -> usize {
+        let size = size as i64;
+        let i = match self {
+            Wrap::Repeat => i.rem_euclid(size),
+            Wrap::Clamp => i.clamp(0, size - 1),
+            Wrap::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                if i < size {
+                    i
+                } else {
+                    2 * size - 1 - i
+                }
+            }
+        };
+        i as usize
+    }
+}
+
+/// How pixels are blended when looking up an image texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Filter {
+    /// The closest pixel
+    Nearest,
+    /// The four closest pixels
+    Bilinear,
+    /// Bilinear lookups in the two mipmap levels closest to the size of the ray's footprint, so
+    /// distant textures are averaged instead of aliasing
+    #[default]
+    Trilinear,
+}
+
+/// One level of the mipmap: the image, or a downscaled copy of it. Texels are linear.
+struct MipLevel {
+    width: u32,
+    height: u32,
+    texels: Vec<[f32; 3]>,
+}
+
+impl MipLevel {
+    /// Half the size of `self`, averaging each two by two block.
+    fn downsample(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.; 3];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (2 * x + dx).min(self.width - 1);
+                    let sy = (2 * y + dy).min(self.height - 1);
+                    let texel = self.texels[(sy * self.width + sx) as usize];
+                    sum.iter_mut().zip(texel).for_each(|(s, t)| *s += t / 4.);
+                }
+                texels.push(sum);
+            }
+        }
+        Self {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64, wrap: Wrap) -> Color {
+        let x = wrap.apply(x, self.width);
+        let y = wrap.apply(y, self.height);
+        let [r, g, b] = self.texels[y * self.width as usize + x];
+        Color::new(r as f64, g as f64, b as f64)
+    }
+
+    fn nearest(&self, uv: Vec2, wrap: Wrap) -> Color {
+        // Image rows run top to bottom, while v runs bottom to top
+        let x = (uv.x * self.width as f64).floor() as i64;
+        let y = ((1. - uv.y) * self.height as f64).floor() as i64;
+        self.texel(x, y, wrap)
+    }
+
+    fn bilinear(&self, uv: Vec2, wrap: Wrap) -> Color {
+        // Pixel centers are at half-integer coordinates
+        let x = uv.x * self.width as f64 - 0.5;
+        let y = (1. - uv.y) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1. - tx) * self.texel(x0, y0, wrap) + tx * self.texel(x0 + 1, y0, wrap);
+        let bottom =
+            (1. - tx) * self.texel(x0, y0 + 1, wrap) + tx * self.texel(x0 + 1, y0 + 1, wrap);
+        (1. - ty) * top + ty * bottom
+    }
+}
+
+/// Texture read from an image file, mapped over the surface's texture coordinates.
+///
+/// PNG, JPEG, HDR and the other formats supported by the `image` crate can be loaded. 8 and 16
+/// bit images are assumed to be sRGB encoded and are converted to linear, floating point images
+/// are used as is.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "ImageTextureFile", into = "ImageTextureFile")
+)]
+pub struct ImageTexture {
+    path: PathBuf,
+    srgb: bool,
+    wrap: Wrap,
+    filter: Filter,
+    /// Full size image first, down to 1x1
+    levels: Arc<[MipLevel]>,
+}
+
+impl ImageTexture {
+    /// Load an image holding colors. Relative paths are relative to the working directory.
+    pub fn load<T>(path: T) -> image::ImageResult<Self>
+    where
+        T: AsRef<Path>,
+    {
+        Self::open(path.as_ref(), true)
+    }
+
+    /// Load an image holding data rather than colors, such as a roughness map, skipping the sRGB
+    /// conversion.
+    pub fn load_linear<T>(path: T) -> image::ImageResult<Self>
+    where
+        T: AsRef<Path>,
+    {
+        Self::open(path.as_ref(), false)
+    }
+
+    fn open(path: &Path, srgb: bool) -> image::ImageResult<Self> {
+        let image = image::open(path)?;
+        let encoded = srgb
+            && !matches!(
+                image,
+                DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+            );
+        let image = image.into_rgb32f();
+
+        let decode = |c: f32| if encoded { srgb_to_linear(c) } else { c };
+        let mut levels = vec![MipLevel {
+            width: image.width(),
+            height: image.height(),
+            texels: image.pixels().map(|pixel| pixel.0.map(decode)).collect(),
+        }];
+        loop {
+            let level = &levels[levels.len() - 1];
+            if level.width <= 1 && level.height <= 1 {
+                break;
+            }
+            levels.push(level.downsample());
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            srgb,
+            wrap: Wrap::default(),
+            filter: Filter::default(),
+            levels: levels.into(),
+        })
+    }
+
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Level of detail for a footprint, as a fractional mipmap level.
+    fn lod(&self, uv_footprint: Vec2) -> f64 {
+        let level = &self.levels[0];
+        let texels =
+            (uv_footprint * Vec2::new(level.width as f64, level.height as f64)).max_element();
+        if texels > 1. {
+            texels.log2().min((self.levels.len() - 1) as f64)
+        } else {
+            0.
+        }
+    }
+}
+
+/// Decode an sRGB encoded channel.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("path", &self.path)
+            .field("srgb", &self.srgb)
+            .field("wrap", &self.wrap)
+            .field("filter", &self.filter)
+            .field("width", &self.levels[0].width)
+            .field("height", &self.levels[0].height)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "image"))]
+impl Texture for ImageTexture {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let level = &self.levels[0];
+        if level.texels.is_empty() {
+            // Debugging aid for missing image data
+            return Color::new(0., 1., 1.);
+        }
+
+        match self.filter {
+            Filter::Nearest => level.nearest(coords.uv, self.wrap),
+            Filter::Bilinear => level.bilinear(coords.uv, self.wrap),
+            Filter::Trilinear => {
+                let lod = self.lod(coords.uv_footprint);
+                let lower = lod.floor();
+                let t = lod - lower;
+                let lower = lower as usize;
+                let color = self.levels[lower].bilinear(coords.uv, self.wrap);
+                if t > 0. {
+                    let upper = self.levels[lower + 1].bilinear(coords.uv, self.wrap);
+                    (1. - t) * color + t * upper
+                } else {
+                    color
+                }
+            }
+        }
+    }
+}
+
+/// How an image texture is stored in scene files: the path and lookup settings, loaded on
+/// deserialization.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageTextureFile {
+    path: PathBuf,
+    /// Set to false for images holding data rather than colors
+    #[serde(default = "default_srgb")]
+    srgb: bool,
+    #[serde(default)]
+    wrap: Wrap,
+    #[serde(default)]
+    filter: Filter,
+}
+
+#[cfg(feature = "serde")]
+fn default_srgb() -> bool {
+    true
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ImageTextureFile> for ImageTexture {
+    type Error = String;
+
+    fn try_from(file: ImageTextureFile) -> Result<Self, Self::Error> {
+        ImageTexture::open(&file.path, file.srgb)
+            .map(|texture| texture.wrap(file.wrap).filter(file.filter))
+            .map_err(|err| format!("failed to load {}: {err}", file.path.display()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ImageTexture> for ImageTextureFile {
+    fn from(texture: ImageTexture) -> Self {
+        Self {
+            path: texture.path,
+            srgb: texture.srgb,
+            wrap: texture.wrap,
+            filter: texture.filter,
+        }
+    }
+}
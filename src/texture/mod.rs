@@ -0,0 +1,129 @@
+pub mod channel;
+pub mod checker;
+pub mod image;
+pub mod noise;
+pub mod solid_color;
+
+use std::fmt::Debug;
+
+use crate::{Color, Vec2, Vec3};
+
+pub use self::image::{Filter, ImageTexture, Wrap};
+pub use channel::{Channel, ColorChannel};
+pub use checker::{Checker, UvChecker};
+pub use noise::{Fractal, Marble, Noise, Perlin, Wood};
+pub use solid_color::SolidColor;
+
+/// Where a texture is being looked up.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextureCoords {
+    /// Surface texture coordinates
+    pub uv: Vec2,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Width of the area being shaded, in world units. Zero means a single point.
+    pub footprint: f64,
+    /// Width of the area being shaded along `u` and `v`
+    pub uv_footprint: Vec2,
+}
+
+impl TextureCoords {
+    /// Coordinates of a single point, with no filtering.
+    pub fn new(uv: Vec2, point: Vec3) -> Self {
+        Self {
+            uv,
+            point,
+            ..Default::default()
+        }
+    }
+}
+
+/// A color that varies over a surface, looked up by texture coordinates or position.
+///
+/// Like materials, textures are shared as `Arc<dyn Texture>` and need `#[typetag::serde]` on
+/// their `impl Texture` block with the `serde` feature.
+#[cfg_attr(feature = "serde", typetag::serde(tag = "type"))]
+pub trait Texture: Debug + Send + Sync {
+    /// Color averaged over the footprint in `coords`. Textures that can't filter may just return
+    /// the color at the center.
+    fn value(&self, coords: &TextureCoords) -> Color;
+
+    /// Scalar value for textures driving a single parameter, such as metal fuzz. This is the mean
+    /// of the color channels.
+    fn scalar(&self, coords: &TextureCoords) -> f64 {
+        self.value(coords).element_sum() / 3.
+    }
+
+    /// The color of the texture if it is the same everywhere.
+    fn constant(&self) -> Option<Color> {
+        None
+    }
+}
+
+/// Serde helpers so texture parameters can be written as a plain color, a plain number, or a
+/// texture table, e.g. `albedo = [0.8, 0.1, 0.1]` or
+/// `albedo = { type = "checker", scale = 0.3, even = [0, 0, 0], odd = [1, 1, 1] }`.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_texture {
+    use std::fmt;
+    use std::sync::Arc;
+
+    use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+    use serde::de::{self, MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{SolidColor, Texture};
+    use crate::Color;
+
+    pub fn serialize<S>(texture: &Arc<dyn Texture>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match texture.constant() {
+            Some(color) if color.x == color.y && color.y == color.z => {
+                serializer.serialize_f64(color.x)
+            }
+            Some(color) => color.serialize(serializer),
+            None => texture.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Arc<dyn Texture>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TextureVisitor;
+
+        impl<'de> Visitor<'de> for TextureVisitor {
+            type Value = Arc<dyn Texture>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a number, a color or a texture")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                Ok(Arc::new(SolidColor::new(Color::splat(value))))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                self.visit_f64(value as f64)
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                self.visit_f64(value as f64)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let color = Color::deserialize(SeqAccessDeserializer::new(seq))?;
+                Ok(Arc::new(SolidColor::new(color)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let texture = Box::<dyn Texture>::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(texture.into())
+            }
+        }
+
+        deserializer.deserialize_any(TextureVisitor)
+    }
+}
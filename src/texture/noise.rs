@@ -0,0 +1,303 @@
+//! Procedural textures built on Perlin noise, for surfaces that don't need an image.
+//!
+//! Each texture owns a [`Perlin`] generator created from a seed, so the same seed always gives
+//! the same pattern. In scene files only the seed is stored.
+
+use std::f64::consts::PI;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{Texture, TextureCoords};
+use crate::vectors::random_unit_vector;
+use crate::{Color, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise: random unit gradients on the integer lattice, smoothly interpolated between.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "u64", into = "u64"))]
+pub struct Perlin {
+    seed: u64,
+    gradients: Box<[Vec3; POINT_COUNT]>,
+    /// Permutation of the lattice coordinates along each axis
+    permutations: Box<[[u8; POINT_COUNT]; 3]>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = Box::new([(); POINT_COUNT].map(|_| random_unit_vector(&mut rng)));
+        let permutations = Box::new([(); 3].map(|_| {
+            let mut permutation = [0; POINT_COUNT];
+            permutation
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, p)| *p = i as u8);
+            permutation.shuffle(&mut rng);
+            permutation
+        }));
+        Self {
+            seed,
+            gradients,
+            permutations,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Noise at `point`, roughly in `-1..1`. Zero at every lattice point.
+    pub fn noise(&self, point: Vec3) -> f64 {
+        let cell = point.floor();
+        let local = point - cell;
+        let cell = cell.as_i64vec3();
+        // Hermite smoothing so the gradient is continuous across cells
+        let weight = local * local * (3. - 2. * local);
+
+        let mut sum = 0.;
+        for (di, dj, dk) in itertools::iproduct!(0..2, 0..2, 0..2) {
+            let [px, py, pz] = self.permutations.as_ref();
+            let index = px[((cell.x + di) & 255) as usize]
+                ^ py[((cell.y + dj) & 255) as usize]
+                ^ pz[((cell.z + dk) & 255) as usize];
+            let corner = Vec3::new(di as f64, dj as f64, dk as f64);
+            let falloff = (corner * weight + (1. - corner) * (1. - weight)).element_product();
+            sum += falloff * self.gradients[index as usize].dot(local - corner);
+        }
+        sum
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each at twice the frequency and half
+    /// the amplitude of the last. Roughly in `-1..1`.
+    pub fn fbm(&self, point: Vec3, octaves: u32) -> f64 {
+        self.octaves(point, octaves, |noise| noise)
+    }
+
+    /// Like [`Perlin::fbm`], but summing the absolute value of each layer, which gives creases
+    /// where the noise crosses zero. Roughly in `0..1`.
+    pub fn turbulence(&self, point: Vec3, octaves: u32) -> f64 {
+        self.octaves(point, octaves, f64::abs)
+    }
+
+    fn octaves(&self, mut point: Vec3, octaves: u32, layer: impl Fn(f64) -> f64) -> f64 {
+        let mut sum = 0.;
+        let mut weight = 1.;
+        for _ in 0..octaves {
+            sum += weight * layer(self.noise(point));
+            weight *= 0.5;
+            point *= 2.;
+        }
+        sum
+    }
+}
+
+impl fmt::Debug for Perlin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Perlin").field("seed", &self.seed).finish()
+    }
+}
+
+impl From<u64> for Perlin {
+    fn from(seed: u64) -> Self {
+        Perlin::new(seed)
+    }
+}
+
+impl From<Perlin> for u64 {
+    fn from(perlin: Perlin) -> Self {
+        perlin.seed
+    }
+}
+
+/// How the layers of a [`Noise`] texture are summed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Fractal {
+    /// Soft, cloudy noise
+    #[default]
+    Fbm,
+    /// Billowy noise with sharp creases
+    Turbulence,
+}
+
+/// Blend between two colors driven by fractal noise.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Noise {
+    #[cfg_attr(feature = "serde", serde(rename = "seed"))]
+    pub perlin: Perlin,
+    /// Frequency of the noise: features are roughly `1 / scale` across
+    pub scale: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_octaves"))]
+    pub octaves: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fractal: Fractal,
+    #[cfg_attr(feature = "serde", serde(default = "black"))]
+    pub low: Color,
+    #[cfg_attr(feature = "serde", serde(default = "white"))]
+    pub high: Color,
+}
+
+impl Noise {
+    pub fn new(seed: u64, scale: f64) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            octaves: default_octaves(),
+            fractal: Fractal::default(),
+            low: black(),
+            high: white(),
+        }
+    }
+
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn fractal(mut self, fractal: Fractal) -> Self {
+        self.fractal = fractal;
+        self
+    }
+
+    pub fn colors(mut self, low: Color, high: Color) -> Self {
+        self.low = low;
+        self.high = high;
+        self
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "noise"))]
+impl Texture for Noise {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let point = self.scale * coords.point;
+        let t = match self.fractal {
+            Fractal::Fbm => 0.5 + 0.5 * self.perlin.fbm(point, self.octaves),
+            Fractal::Turbulence => self.perlin.turbulence(point, self.octaves),
+        };
+        self.low.lerp(self.high, t.clamp(0., 1.))
+    }
+}
+
+/// Marble: bands along Z, distorted by turbulence into veins.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Marble {
+    #[cfg_attr(feature = "serde", serde(rename = "seed"))]
+    pub perlin: Perlin,
+    /// Frequency of the bands
+    pub scale: f64,
+    /// How far the turbulence pushes the bands around
+    #[cfg_attr(feature = "serde", serde(default = "default_marble_distortion"))]
+    pub distortion: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_octaves"))]
+    pub octaves: u32,
+    #[cfg_attr(feature = "serde", serde(default = "white"))]
+    pub base: Color,
+    #[cfg_attr(feature = "serde", serde(default = "black"))]
+    pub vein: Color,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f64, base: Color, vein: Color) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            distortion: default_marble_distortion(),
+            octaves: default_octaves(),
+            base,
+            vein,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "marble"))]
+impl Texture for Marble {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let point = self.scale * coords.point;
+        let turbulence = self.perlin.turbulence(point, self.octaves);
+        let t = 0.5 + 0.5 * (point.z + self.distortion * turbulence).sin();
+        self.vein.lerp(self.base, t)
+    }
+}
+
+/// Wood: concentric rings around the Y axis, wobbled by turbulence.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Wood {
+    #[cfg_attr(feature = "serde", serde(rename = "seed"))]
+    pub perlin: Perlin,
+    /// Rings per unit of distance from the axis
+    pub rings: f64,
+    /// Frequency of the wobble
+    #[cfg_attr(feature = "serde", serde(default = "default_wood_scale"))]
+    pub scale: f64,
+    /// How far the wobble pushes the rings around, in rings
+    #[cfg_attr(feature = "serde", serde(default = "default_wood_distortion"))]
+    pub distortion: f64,
+    #[cfg_attr(feature = "serde", serde(default = "default_octaves"))]
+    pub octaves: u32,
+    pub light: Color,
+    pub dark: Color,
+}
+
+impl Wood {
+    pub fn new(seed: u64, rings: f64, light: Color, dark: Color) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            rings,
+            scale: default_wood_scale(),
+            distortion: default_wood_distortion(),
+            octaves: default_octaves(),
+            light,
+            dark,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "wood"))]
+impl Texture for Wood {
+    fn value(&self, coords: &TextureCoords) -> Color {
+        let point = coords.point;
+        let turbulence = self.perlin.turbulence(self.scale * point, self.octaves);
+        let ring = point.x.hypot(point.z) * self.rings + self.distortion * turbulence;
+        // Thin dark late wood at the end of each ring, fading into light early wood
+        let t = (0.5 - 0.5 * (2. * PI * ring).cos()).powi(4);
+        self.light.lerp(self.dark, t)
+    }
+}
+
+fn default_octaves() -> u32 {
+    7
+}
+
+fn default_marble_distortion() -> f64 {
+    10.
+}
+
+fn default_wood_scale() -> f64 {
+    2.
+}
+
+fn default_wood_distortion() -> f64 {
+    1.
+}
+
+fn black() -> Color {
+    Color::ZERO
+}
+
+fn white() -> Color {
+    Color::ONE
+}
@@ -0,0 +1,29 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::texture::{Texture, TextureCoords};
+use crate::Color;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde(name = "solid_color"))]
+impl Texture for SolidColor {
+    fn value(&self, _coords: &TextureCoords) -> Color {
+        self.color
+    }
+
+    fn constant(&self) -> Option<Color> {
+        Some(self.color)
+    }
+}
@@ -12,17 +12,17 @@ pub fn refract(uv: Vec3, normal: Vec3, n1_over_n2: f64) -> Vec3 {
     r_out_perp + r_out_parallel
 }
 
-pub fn sample_square(rng: &mut impl Rng) -> Vec2 {
+pub fn sample_square(rng: &mut (impl Rng + ?Sized)) -> Vec2 {
     let vec: Vec2 = rng.sample(Standard);
     vec - 0.5
 }
 
-pub fn random_unit_vector(rng: &mut impl Rng) -> Vec3 {
+pub fn random_unit_vector(rng: &mut (impl Rng + ?Sized)) -> Vec3 {
     let unit_sphere = UnitSphere.sample(rng);
     Vec3::from_array(unit_sphere)
 }
 
-pub fn random_unit_hemisphere(normal: Vec3, rng: &mut impl Rng) -> Vec3 {
+pub fn random_unit_hemisphere(normal: Vec3, rng: &mut (impl Rng + ?Sized)) -> Vec3 {
     let vec = random_unit_vector(rng);
     if vec.dot(normal) > 0. {
         vec
@@ -31,12 +31,34 @@ pub fn random_unit_hemisphere(normal: Vec3, rng: &mut impl Rng) -> Vec3 {
     }
 }
 
-pub fn random_in_unit_disc(rng: &mut impl Rng) -> Vec2 {
+/// Cosine-weighted direction on the hemisphere around +Z.
+pub fn random_cosine_direction(rng: &mut (impl Rng + ?Sized)) -> Vec3 {
+    let phi = 2. * std::f64::consts::PI * rng.gen::<f64>();
+    let r2: f64 = rng.gen();
+    let r = r2.sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, (1. - r2).sqrt())
+}
+
+/// Uniformly distributed direction within `cos_theta_max` of the unit `axis`.
+pub fn random_in_cone(axis: Vec3, cos_theta_max: f64, rng: &mut (impl Rng + ?Sized)) -> Vec3 {
+    let cos_theta = 1. + rng.gen::<f64>() * (cos_theta_max - 1.);
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * std::f64::consts::PI * rng.gen::<f64>();
+    let (u, v) = axis.any_orthonormal_pair();
+    sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * axis
+}
+
+/// Probability density of `random_in_cone`, per unit solid angle.
+pub fn cone_pdf(cos_theta_max: f64) -> f64 {
+    1. / (2. * std::f64::consts::PI * (1. - cos_theta_max))
+}
+
+pub fn random_in_unit_disc(rng: &mut (impl Rng + ?Sized)) -> Vec2 {
     let unit_disc = UnitDisc.sample(rng);
     Vec2::from_array(unit_disc)
 }
 
-pub fn random_in_range(range: Range<f64>, rng: &mut impl Rng) -> Vec3 {
+pub fn random_in_range(range: Range<f64>, rng: &mut (impl Rng + ?Sized)) -> Vec3 {
     // let mut rng = thread_rng();
 
     // let arr: [f64; 3] = rng.gen();
@@ -0,0 +1,70 @@
+#![cfg(feature = "cli")]
+
+//! Runs the `ray-tow` binary to check how it reads its arguments.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn ray_tow(output: &str, resolution: &str) -> (Output, PathBuf) {
+    let path = std::env::temp_dir().join(format!("ray-tow-cli-{}-{output}", std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_ray-tow"))
+        .args([
+            "scenes/three-spheres.toml",
+            "--spp",
+            "1",
+            "--max-depth",
+            "2",
+        ])
+        .arg("--output")
+        .arg(&path)
+        .arg(format!("--resolution={resolution}"))
+        .output()
+        .unwrap();
+    (output, path)
+}
+
+fn rendered_size(name: &str, resolution: &str) -> (u32, u32) {
+    let (output, path) = ray_tow(name, resolution);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let size = image::image_dimensions(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    size
+}
+
+#[test]
+fn resolution_sets_width_and_height() {
+    assert_eq!(rendered_size("both.png", "32x8"), (32, 8));
+}
+
+#[test]
+fn resolution_without_height_keeps_the_aspect_ratio() {
+    // three-spheres.toml is 3:2
+    assert_eq!(rendered_size("width.png", "30"), (30, 20));
+}
+
+#[test]
+fn invalid_resolutions_are_rejected() {
+    for resolution in [
+        "0x100",
+        "100x0",
+        "0",
+        "1920x",
+        "x1080",
+        "wide",
+        "1920xtall",
+        "-5x5",
+    ] {
+        let (output, path) = ray_tow("invalid.png", resolution);
+        assert!(!output.status.success(), "{resolution} was accepted");
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("invalid resolution"),
+            "{resolution}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(!path.exists());
+    }
+}
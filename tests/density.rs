@@ -0,0 +1,144 @@
+//! Checks density fields: that voxel grids interpolate trilinearly between voxel centers, hold
+//! their edge values out to the faces and reject corrupt files, and that noise stays within the
+//! bound that rays are tracked with.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ray_tow::density::{DensityField, NoiseDensity, VoxelGrid};
+use ray_tow::texture::Fractal;
+use ray_tow::Vec3;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ray-tow-density-{}-{name}.vol", std::process::id()))
+}
+
+fn grid(name: &str, resolution: [u32; 3], densities: &[f32]) -> VoxelGrid {
+    let path = temp_path(name);
+    VoxelGrid::save(&path, resolution, densities).unwrap();
+    let grid = VoxelGrid::load(&path).unwrap();
+    fs::remove_file(path).unwrap();
+    grid
+}
+
+fn load_bytes(name: &str, bytes: &[u8]) -> io::Result<VoxelGrid> {
+    let path = temp_path(name);
+    fs::write(&path, bytes).unwrap();
+    let grid = VoxelGrid::load(&path);
+    fs::remove_file(path).unwrap();
+    grid
+}
+
+#[test]
+fn voxel_grids_interpolate_between_voxel_centers() {
+    // Each voxel's density is its index, which trilinear interpolation keeps linear in between
+    let grid = grid("linear", [2, 2, 2], &[0., 1., 2., 3., 4., 5., 6., 7.]);
+    assert_eq!(grid.resolution(), [2, 2, 2]);
+    assert_eq!(grid.max_density(), 7.);
+    let linear = |point: Vec3| {
+        let voxel = 2. * point - 0.5;
+        voxel.x + 2. * voxel.y + 4. * voxel.z
+    };
+    for point in [
+        Vec3::splat(0.25),
+        Vec3::splat(0.75),
+        Vec3::new(0.75, 0.25, 0.25),
+        Vec3::splat(0.5),
+        Vec3::new(0.3, 0.6, 0.45),
+        Vec3::new(0.7, 0.4, 0.26),
+    ] {
+        let density = grid.density(point);
+        assert!(
+            (density - linear(point)).abs() < 1e-12,
+            "{density} at {point} instead of {}",
+            linear(point)
+        );
+    }
+}
+
+#[test]
+fn voxel_grids_hold_their_edge_values_out_to_the_faces() {
+    let grid = grid("faces", [2, 1, 1], &[0.2, 1.]);
+    for (x, expected) in [
+        (0., 0.2),
+        (0.1, 0.2),
+        (0.25, 0.2),
+        (0.5, 0.6),
+        (0.75, 1.),
+        (0.9, 1.),
+        (1., 1.),
+    ] {
+        // A single voxel across Y and Z is the same everywhere along them
+        for (y, z) in [(0., 0.), (0.5, 0.5), (1., 0.3)] {
+            let density = grid.density(Vec3::new(x, y, z));
+            assert!(
+                (density - expected).abs() < 1e-6,
+                "{density} at ({x}, {y}, {z}) instead of {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn corrupt_voxel_grids_are_rejected() {
+    let header = |resolution: [u32; 3]| {
+        resolution
+            .iter()
+            .flat_map(|size| size.to_le_bytes())
+            .collect::<Vec<u8>>()
+    };
+    let mut truncated = header([2, 2, 2]);
+    truncated.extend([0; 4 * 7]);
+    let cases = [
+        ("short", vec![0; 8]),
+        ("empty", header([0, 4, 4])),
+        ("truncated", truncated),
+        // So large that counting its bytes overflows
+        ("huge", header([u32::MAX; 3])),
+    ];
+    for (name, bytes) in cases {
+        let err = load_bytes(name, &bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{name}: {err}");
+    }
+}
+
+#[test]
+fn noise_density_stays_within_its_bound() {
+    let max = NoiseDensity::new(47, 4.).max_density();
+    for noise in [
+        NoiseDensity::new(47, 4.),
+        NoiseDensity::new(47, 4.).fade(false),
+        NoiseDensity::new(47, 4.).fractal(Fractal::Turbulence),
+    ] {
+        let mut total = 0.;
+        let steps = 16;
+        for (x, y, z) in itertools::iproduct!(0..=steps, 0..=steps, 0..=steps) {
+            let point = Vec3::new(x as f64, y as f64, z as f64) / steps as f64;
+            let density = noise.density(point);
+            assert!((0. ..=max).contains(&density), "{density} at {point}");
+            total += density;
+        }
+        assert!(total > 0., "{noise:?} is empty");
+    }
+}
+
+#[test]
+fn noise_density_fades_out_at_the_corners() {
+    let noise = NoiseDensity::new(47, 4.);
+    for (x, y, z) in itertools::iproduct!([0., 1.], [0., 1.], [0., 1.]) {
+        assert_eq!(noise.density(Vec3::new(x, y, z)), 0.);
+    }
+
+    // And it's the same noise for the same seed, but not for another
+    let point = Vec3::new(0.4, 0.5, 0.6);
+    assert_eq!(
+        noise.density(point),
+        NoiseDensity::new(47, 4.).density(point)
+    );
+    let other = NoiseDensity::new(48, 4.).fade(false);
+    assert!((0..10).any(|i| {
+        let point = Vec3::splat(0.1 * i as f64);
+        other.density(point) != noise.clone().fade(false).density(point)
+    }));
+}
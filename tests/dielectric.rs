@@ -0,0 +1,111 @@
+//! Statistical checks that `Dielectric` splits light between reflection and refraction as the
+//! Fresnel equations say, without creating or losing any.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::hit_record::{FaceSide, HitRecord};
+use ray_tow::material::{Dielectric, Fresnel, Material, ThinFilm};
+use ray_tow::ray::Ray;
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 100_000;
+const ANGLES: [f64; 6] = [0., 20., 40., 60., 80., 89.];
+
+/// Mean attenuation, and the fraction of rays reflected, for light arriving at `angle` degrees from
+/// the normal.
+fn scatter_statistics(material: &dyn Material, angle: f64, face_side: FaceSide) -> (Color, f64) {
+    let angle = angle.to_radians();
+    let ray = Ray::new(
+        Vec3::new(-angle.sin(), 0., angle.cos()),
+        Vec3::new(angle.sin(), 0., -angle.cos()),
+    );
+    let hit_record = HitRecord {
+        normal: Vec3::Z,
+        t: 1.,
+        face_side,
+        ..Default::default()
+    };
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut attenuation = Color::ZERO;
+    let mut reflected = 0;
+    for _ in 0..SAMPLES {
+        let (scattered, color) = material
+            .scatter(&ray, &hit_record, &mut rng)
+            .expect("dielectrics always scatter");
+        attenuation += color;
+        if scattered.direction.dot(hit_record.normal) > 0. {
+            reflected += 1;
+        }
+    }
+    (
+        attenuation / SAMPLES as f64,
+        reflected as f64 / SAMPLES as f64,
+    )
+}
+
+/// Reference unpolarized Fresnel reflectance going from index `n1` into `n2`.
+fn fresnel(cos_i: f64, n1: f64, n2: f64) -> f64 {
+    let sin_t = n1 / n2 * (1. - cos_i * cos_i).sqrt();
+    if sin_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin_t * sin_t).sqrt();
+    let s = (n1 * cos_i - n2 * cos_t) / (n1 * cos_i + n2 * cos_t);
+    let p = (n2 * cos_i - n1 * cos_t) / (n2 * cos_i + n1 * cos_t);
+    0.5 * (s * s + p * p)
+}
+
+/// Whether a sampled fraction is within five standard deviations of `probability`.
+fn close(fraction: f64, probability: f64) -> bool {
+    let sigma = (probability * (1. - probability) / SAMPLES as f64).sqrt();
+    (fraction - probability).abs() <= 5. * sigma + 1e-9
+}
+
+#[test]
+fn exact_fresnel_matches_reflected_fraction() {
+    let glass = Dielectric::new(1.5).fresnel(Fresnel::Exact);
+    for angle in ANGLES {
+        let cos_i = angle.to_radians().cos();
+        for (face_side, n1, n2) in [(FaceSide::Front, 1., 1.5), (FaceSide::Back, 1.5, 1.)] {
+            let (attenuation, reflected) = scatter_statistics(&glass, angle, face_side);
+            let expected = fresnel(cos_i, n1, n2);
+            assert!(
+                close(reflected, expected),
+                "{face_side:?} at {angle}°: reflected {reflected}, expected {expected}"
+            );
+            assert_eq!(attenuation, Color::ONE);
+        }
+    }
+}
+
+#[test]
+fn schlick_matches_reflected_fraction() {
+    let glass = Dielectric::new(1.5);
+    for angle in ANGLES {
+        let cos_i = angle.to_radians().cos();
+        let (_, reflected) = scatter_statistics(&glass, angle, FaceSide::Front);
+        let r0 = (0.5_f64 / 2.5).powi(2);
+        let expected = r0 + (1. - r0) * (1. - cos_i).powi(5);
+        assert!(
+            close(reflected, expected),
+            "at {angle}°: reflected {reflected}, expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn thin_film_conserves_energy() {
+    let coated = Dielectric::new(1.5).film(ThinFilm::new(350., 1.33));
+    for angle in ANGLES {
+        for face_side in [FaceSide::Front, FaceSide::Back] {
+            // Reflection and refraction are reweighted per channel, so the mean is only 1 on
+            // average
+            let (attenuation, _) = scatter_statistics(&coated, angle, face_side);
+            assert!(
+                (attenuation - Color::ONE).abs().max_element() < 0.02,
+                "{face_side:?} at {angle}°: mean attenuation {attenuation}"
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "serde")]
+
+use std::io::{BufRead, BufReader};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ray_tow::camera::Camera;
+use ray_tow::distributed::{self, Coordinator};
+use ray_tow::material::{Dielectric, Lambertian, Metal};
+use ray_tow::shapes::{sphere::Sphere, Shape};
+use ray_tow::Vec3;
+
+fn scene() -> (Camera, Vec<Shape>) {
+    let world = vec![
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., -100.5, -1.),
+            100.,
+            Arc::new(Lambertian::new(Vec3::new(0.8, 0.8, 0.))),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., 0., -1.),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(1., 0., -1.),
+            0.5,
+            Arc::new(Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.3)),
+        )),
+    ];
+    let camera = Camera::init()
+        .image_width(48)
+        .samples_per_pixel(4)
+        .seed(7)
+        .build();
+    (camera, world)
+}
+
+fn spawn_worker() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || distributed::serve::<Vec<Shape>>(listener));
+    addr
+}
+
+/// A worker that accepts the scene and one tile, then hangs up without answering.
+fn spawn_flaky_worker() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut line = String::new();
+            for _ in 0..2 {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+        }
+    });
+    addr
+}
+
+/// A worker that stays connected but never answers.
+fn spawn_hung_worker() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let streams: Vec<_> = listener.incoming().collect();
+        drop(streams);
+    });
+    addr
+}
+
+/// An address with nothing listening on it.
+fn dead_worker() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+#[test]
+fn matches_local_render() {
+    let (camera, world) = scene();
+    let workers = vec![spawn_worker(), spawn_worker(), spawn_worker()];
+
+    let distributed = Coordinator::new(workers)
+        .tile_size(8)
+        .render(&camera, &world)
+        .unwrap();
+    let local = camera.render(&world);
+
+    assert_eq!(distributed.buf, local.buf);
+}
+
+#[test]
+fn redispatches_tiles_from_failed_workers() {
+    let (camera, world) = scene();
+    let workers = vec![spawn_flaky_worker(), dead_worker(), spawn_worker()];
+
+    let distributed = Coordinator::new(workers)
+        .tile_size(8)
+        .render(&camera, &world)
+        .unwrap();
+    let local = camera.render(&world);
+
+    assert_eq!(distributed.buf, local.buf);
+}
+
+#[test]
+fn redispatches_tiles_from_hung_workers() {
+    let (camera, world) = scene();
+    let workers = vec![spawn_hung_worker(), spawn_worker()];
+
+    let distributed = Coordinator::new(workers)
+        .tile_size(8)
+        .timeout(Duration::from_millis(200))
+        .render(&camera, &world)
+        .unwrap();
+    let local = camera.render(&world);
+
+    assert_eq!(distributed.buf, local.buf);
+}
+
+#[test]
+fn fails_when_every_worker_fails() {
+    let (camera, world) = scene();
+    let workers = vec![spawn_flaky_worker(), dead_worker()];
+
+    let result = Coordinator::new(workers).render(&camera, &world);
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,210 @@
+//! Statistical checks that sampling shapes as lights picks directions that hit them, with
+//! densities matching the solid angle they cover, and that lights without geometry light surfaces
+//! as brightly as they should, through volumes too.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::background::Background;
+use ray_tow::camera::{Camera, Tile};
+use ray_tow::hit_record::Hittable;
+use ray_tow::light::{
+    power_heuristic, Light, LightSample, Lights, PointLight, SpotLight, SunLight,
+};
+use ray_tow::material::{DiffuseLight, Lambertian};
+use ray_tow::ray::Ray;
+use ray_tow::shapes::constant_medium::ConstantMedium;
+use ray_tow::shapes::sphere::Sphere;
+use ray_tow::shapes::triangle::Triangle;
+use ray_tow::shapes::Shape;
+use ray_tow::vectors::random_unit_vector;
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 100_000;
+
+fn light() -> Arc<DiffuseLight> {
+    Arc::new(DiffuseLight::new(Color::ONE))
+}
+
+/// Solid angle covered by `shape` seen from `origin`, estimated from sampled directions and their
+/// densities, and from uniformly random directions.
+fn solid_angles(shape: &Shape, origin: Vec3) -> (f64, f64) {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut sampled = 0.;
+    for _ in 0..SAMPLES {
+        let direction = shape.sample_direction(origin, &mut rng).unwrap();
+        assert!(
+            shape
+                .hit(&Ray::new(origin, direction), 0.001..f64::INFINITY)
+                .is_some(),
+            "sampled direction {direction} misses {shape:?}"
+        );
+        sampled += 1. / shape.pdf(origin, direction);
+    }
+
+    let mut hits = 0;
+    for _ in 0..SAMPLES {
+        let ray = Ray::new(origin, random_unit_vector(&mut rng));
+        if shape.hit(&ray, 0.001..f64::INFINITY).is_some() {
+            hits += 1;
+        }
+    }
+
+    (
+        sampled / SAMPLES as f64,
+        4. * PI * hits as f64 / SAMPLES as f64,
+    )
+}
+
+fn assert_close(shape: &Shape, origin: Vec3) {
+    let (sampled, uniform) = solid_angles(shape, origin);
+    assert!(
+        (sampled - uniform).abs() < 0.02 * 4. * PI,
+        "from {origin}: sampled {sampled}, uniform {uniform}"
+    );
+}
+
+#[test]
+fn sphere_densities_match_solid_angle() {
+    let sphere = Shape::Sphere(Sphere::new(Vec3::new(0., 2., 0.), 1., light()));
+    assert_close(&sphere, Vec3::ZERO);
+    assert_close(&sphere, Vec3::new(0.5, 0., 3.));
+    // From inside, every direction hits
+    assert_close(&sphere, Vec3::new(0., 2.3, 0.2));
+}
+
+#[test]
+fn triangle_densities_match_solid_angle() {
+    let triangle = Shape::Triangle(Triangle::new(
+        [
+            Vec3::new(-1., 1., -1.),
+            Vec3::new(1., 1., -1.),
+            Vec3::new(0., 1., 1.),
+        ],
+        light(),
+    ));
+    assert_close(&triangle, Vec3::ZERO);
+    assert_close(&triangle, Vec3::new(2., 3., 0.));
+}
+
+#[test]
+fn only_emissive_shapes_are_lights() {
+    let world = vec![
+        Shape::Sphere(Sphere::new(Vec3::ZERO, 1., light())),
+        Shape::Sphere(Sphere::new(
+            Vec3::X,
+            1.,
+            Arc::new(Lambertian::new(Color::ONE)),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::Y,
+            1.,
+            Arc::new(DiffuseLight::new(Color::ZERO)),
+        )),
+    ];
+    assert_eq!(world.lights().len(), 1);
+
+    let lights = Lights::new(world.lights(), &[]);
+    let origin = Vec3::new(0., 0., 5.);
+    let mut rng = StdRng::seed_from_u64(5);
+    let Some(LightSample::Direction(direction)) = lights.sample(origin, &mut rng) else {
+        panic!("shapes are sampled by direction");
+    };
+    assert!(lights.pdf(origin, direction) > 0.);
+    assert_eq!(lights.pdf(origin, Vec3::Z), 0.);
+}
+
+#[test]
+fn power_heuristic_weights_add_up_to_one() {
+    for (a, b) in [(1., 1.), (0.3, 7.), (2., 0.)] {
+        let total = power_heuristic(a, b) + power_heuristic(b, a);
+        assert!((total - 1.).abs() < 1e-12, "{a} and {b} add up to {total}");
+    }
+}
+
+/// Mean radiance of a white floor seen from straight above, lit only by `light`.
+fn floor_radiance(light: impl Into<Light>) -> Color {
+    floor_radiance_through(light, None)
+}
+
+/// Like [`floor_radiance`], with `between` in the way of some of the light.
+fn floor_radiance_through(light: impl Into<Light>, between: Option<Shape>) -> Color {
+    let mut world = vec![Shape::Sphere(Sphere::new(
+        Vec3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::ONE)),
+    ))];
+    world.extend(between);
+    let camera = Camera::init()
+        .position(Vec3::Y)
+        .look_at(Vec3::ZERO)
+        .up(Vec3::Z)
+        .image_width(4)
+        .samples_per_pixel(64)
+        .max_depth(2)
+        .background(Background::Solid { color: Color::ZERO })
+        .light(light)
+        .build();
+    let pixels = camera.render_tile(&world, &Tile::new(0, 0, 4, 2));
+    pixels.iter().sum::<Color>() / pixels.len() as f64
+}
+
+fn assert_radiance(radiance: Color, expected: f64) {
+    assert!(
+        radiance.abs_diff_eq(Color::splat(expected), 0.01 * expected.max(1.)),
+        "radiance {radiance}, expected {expected}"
+    );
+}
+
+#[test]
+fn sun_lights_surfaces_facing_it_by_its_intensity() {
+    let sun = SunLight::new(Vec3::Y, Color::ONE).intensity(2.);
+    assert_radiance(floor_radiance(sun.clone()), 2. / PI);
+    assert_radiance(floor_radiance(sun.angular_diameter(0.)), 2. / PI);
+
+    // Lower in the sky, the same light is spread over more of the floor
+    let low = SunLight::new(Vec3::new(1., 1., 0.), Color::ONE).intensity(2.);
+    assert_radiance(floor_radiance(low), 2. / PI * 0.5f64.sqrt());
+}
+
+#[test]
+fn point_and_spot_lights_fall_off_with_distance() {
+    let point = PointLight::new(Vec3::new(0., 2., 0.), Color::ONE).intensity(8.);
+    assert_radiance(floor_radiance(point), 2. / PI);
+
+    let spot = SpotLight::new(Vec3::new(0., 2., 0.), -Vec3::Y, Color::ONE, 30.).intensity(8.);
+    assert_radiance(floor_radiance(spot.clone()), 2. / PI);
+
+    // Pointing away, the floor is outside the cone
+    let away = SpotLight {
+        direction: Vec3::Y,
+        ..spot
+    };
+    assert_radiance(floor_radiance(away), 0.);
+}
+
+#[test]
+fn lights_shine_through_volumes() {
+    let point = || PointLight::new(Vec3::new(0., 2., 0.), Color::ONE).intensity(8.);
+    // Above the camera, so only light on its way to the floor goes through
+    let volume = |density, albedo| {
+        let boundary = Shape::Sphere(Sphere::new(Vec3::new(0., 1.5, 0.), 0.3, light()));
+        Some(Shape::ConstantMedium(ConstantMedium::new(
+            boundary, density, albedo,
+        )))
+    };
+    let clear = floor_radiance(point());
+
+    // Thin smoke barely dims it
+    let smoke = floor_radiance_through(point(), volume(0.05, Color::ONE));
+    assert!(
+        smoke.cmple(clear).all() && smoke.cmpge(0.95 * clear).all(),
+        "{smoke} through smoke, {clear} without"
+    );
+
+    // What only absorbs dims it by how far the light goes through
+    let absorbing = floor_radiance_through(point(), volume(0.5, Color::ZERO));
+    assert_radiance(absorbing, 2. / PI * (-0.5f64 * 0.6).exp());
+}
@@ -0,0 +1,165 @@
+//! Statistical checks of the estimators used for scattering media: on average, the distances
+//! sampled or tracked through a medium let through as much light as the Beer–Lambert law says, as
+//! does ratio tracking, and phase function sampling scatters forwards as much as its asymmetry
+//! says.
+
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::density::VoxelGrid;
+use ray_tow::medium::{
+    ratio_tracking, sample_flight, sample_phase, track_flight, transmittance, Density, Flight,
+};
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 100_000;
+
+const ABSORPTION: Color = Color::new(0.2, 0.5, 1.);
+const SCATTERING: Color = Color::new(1., 0.6, 0.1);
+const DISTANCE: f64 = 1.5;
+
+/// Means of the weights of flights that passed and that scattered, counting the others as zero.
+fn flight_means(mut flight: impl FnMut(&mut StdRng) -> Flight) -> (Color, Color) {
+    let mut rng = StdRng::seed_from_u64(45);
+    let (mut passed, mut scattered) = (Color::ZERO, Color::ZERO);
+    for _ in 0..SAMPLES {
+        match flight(&mut rng) {
+            Flight::Passed { weight } => passed += weight,
+            Flight::Scattered { distance, weight } => {
+                assert!(
+                    (0. ..DISTANCE).contains(&distance),
+                    "scattered at {distance}"
+                );
+                scattered += weight;
+            }
+            Flight::Absorbed => {}
+        }
+    }
+    (passed / SAMPLES as f64, scattered / SAMPLES as f64)
+}
+
+fn assert_close(actual: Color, expected: Color, what: &str) {
+    assert!(
+        ((actual - expected) / expected).abs().max_element() < 0.02,
+        "{what}: {actual} instead of {expected}"
+    );
+}
+
+/// The light that scatters somewhere along the way, in a medium of constant density one:
+/// `∫ scattering · T(t) dt` up to `DISTANCE`.
+fn expected_scattered(absorption: Color, scattering: Color) -> Color {
+    let extinction = absorption + scattering;
+    scattering / extinction * (1. - transmittance(extinction, DISTANCE))
+}
+
+/// A box from the origin to (2, 2, 2) filled with a constant `density`, saved as a voxel grid.
+fn constant_density(name: &str, density: f32) -> Density {
+    let path =
+        std::env::temp_dir().join(format!("ray-tow-medium-{}-{name}.vol", std::process::id()));
+    VoxelGrid::save(&path, [2, 2, 2], &[density; 8]).unwrap();
+    let grid = VoxelGrid::load(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    Density {
+        field: Arc::new(grid),
+        min: Vec3::ZERO,
+        max: Vec3::splat(2.),
+    }
+}
+
+#[test]
+fn sampled_flights_match_transmittance() {
+    let (passed, scattered) =
+        flight_means(|rng| sample_flight(ABSORPTION, SCATTERING, DISTANCE, rng));
+    let extinction = ABSORPTION + SCATTERING;
+    assert_close(passed, transmittance(extinction, DISTANCE), "passed");
+    assert_close(
+        scattered,
+        expected_scattered(ABSORPTION, SCATTERING),
+        "scattered",
+    );
+}
+
+#[test]
+fn tracked_flights_match_transmittance() {
+    let density = constant_density("track", 0.5);
+    // Along the box's diagonal, which is longer than the distance travelled
+    let origin = Vec3::splat(0.1);
+    let direction = Vec3::ONE.normalize();
+    let (passed, scattered) = flight_means(|rng| {
+        track_flight(
+            &density, origin, direction, ABSORPTION, SCATTERING, DISTANCE, rng,
+        )
+    });
+    let (absorption, scattering) = (0.5 * ABSORPTION, 0.5 * SCATTERING);
+    assert_close(
+        passed,
+        transmittance(absorption + scattering, DISTANCE),
+        "passed",
+    );
+    assert_close(
+        scattered,
+        expected_scattered(absorption, scattering),
+        "scattered",
+    );
+}
+
+#[test]
+fn ratio_tracking_matches_transmittance() {
+    let density = constant_density("ratio", 0.5);
+    let extinction = ABSORPTION + SCATTERING;
+    let mut rng = StdRng::seed_from_u64(46);
+    let mean = (0..SAMPLES)
+        .map(|_| {
+            ratio_tracking(
+                &density,
+                Vec3::splat(0.1),
+                Vec3::ONE.normalize(),
+                extinction,
+                DISTANCE,
+                &mut rng,
+            )
+        })
+        .sum::<Color>()
+        / SAMPLES as f64;
+    assert_close(
+        mean,
+        transmittance(0.5 * extinction, DISTANCE),
+        "transmittance",
+    );
+
+    // Leaving the box, the rest of the way is clear
+    let mean = (0..SAMPLES)
+        .map(|_| {
+            ratio_tracking(
+                &density,
+                Vec3::new(1., 1., 1.5),
+                Vec3::Z,
+                extinction,
+                10.,
+                &mut rng,
+            )
+        })
+        .sum::<Color>()
+        / SAMPLES as f64;
+    assert_close(mean, transmittance(0.5 * extinction, 0.5), "leaving");
+}
+
+#[test]
+fn phase_sampling_has_the_asymmetry_as_mean_cosine() {
+    let mut rng = StdRng::seed_from_u64(47);
+    let direction = Vec3::new(1., -2., 0.5);
+    for g in [-0.8, -0.3, 0., 0.5, 0.9] {
+        let mut total = 0.;
+        for _ in 0..SAMPLES {
+            let scattered = sample_phase(direction, g, &mut rng);
+            assert!((scattered.length() - 1.).abs() < 1e-9, "{scattered}");
+            total += scattered.dot(direction.normalize());
+        }
+        let mean = total / SAMPLES as f64;
+        assert!(
+            (mean - g).abs() < 0.01,
+            "mean cosine {mean} for asymmetry {g}"
+        );
+    }
+}
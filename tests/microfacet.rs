@@ -0,0 +1,268 @@
+//! Statistical checks of the GGX materials: that `scatter`, `eval` and `pdf` describe the same
+//! distribution. Sampling visible normals has to match the density `pdf` reports, or light sampling
+//! and BSDF sampling get weighted wrongly against each other. Principled materials with a white base
+//! also mustn't reflect more light than arrives.
+//! Also checks coated materials, whose `eval` sums the bounces under the coat in closed form, and
+//! that frosted glass refracts each wavelength by its own index of refraction.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::hit_record::{FaceSide, HitRecord};
+use ray_tow::material::{
+    Coated, ComplexIor, Conductor, Ior, Lambertian, Material, Principled, RoughDielectric,
+};
+use ray_tow::ray::Ray;
+use ray_tow::spectrum::Wavelengths;
+use ray_tow::texture::SolidColor;
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 100_000;
+const ANGLES: [f64; 4] = [0., 30., 60., 80.];
+
+fn materials() -> Vec<(&'static str, Box<dyn Material>, FaceSide)> {
+    let mut materials: Vec<(&'static str, Box<dyn Material>, FaceSide)> = vec![
+        (
+            "smooth gold",
+            Box::new(Conductor::new(ComplexIor::GOLD, 0.5)),
+            FaceSide::Front,
+        ),
+        (
+            "rough gold",
+            Box::new(Conductor::new(ComplexIor::GOLD, 0.9)),
+            FaceSide::Front,
+        ),
+        (
+            "frosted glass, entering",
+            Box::new(RoughDielectric::new(1.5, 0.5)),
+            FaceSide::Front,
+        ),
+        (
+            "frosted glass, leaving",
+            Box::new(RoughDielectric::new(1.5, 0.5)),
+            FaceSide::Back,
+        ),
+    ];
+    materials.extend(
+        principled()
+            .into_iter()
+            .map(|(name, material)| (name, material, FaceSide::Front)),
+    );
+    materials
+}
+
+/// Principled materials with a white base, which shouldn't reflect more light than arrives.
+fn principled() -> Vec<(&'static str, Box<dyn Material>)> {
+    let white = || Principled::new(Color::ONE);
+    vec![
+        ("metallic", Box::new(white().metallic(1.).roughness(0.4))),
+        (
+            "clear-coated",
+            Box::new(white().roughness(0.6).clearcoat(1., 0.2)),
+        ),
+        ("sheen", Box::new(white().roughness(0.8).sheen(1., 0.5))),
+        (
+            "transmissive",
+            Box::new(white().roughness(0.5).transmission(1.)),
+        ),
+    ]
+}
+
+/// Light arriving at `angle` degrees from the normal, in the XZ plane, and a hit with the shading
+/// frame aligned to the axes.
+fn incoming(angle: f64, face_side: FaceSide) -> (Ray, HitRecord<'static>) {
+    let angle = angle.to_radians();
+    let ray = Ray::new(
+        Vec3::new(-angle.sin(), 0., angle.cos()),
+        Vec3::new(angle.sin(), 0., -angle.cos()),
+    );
+    let hit_record = HitRecord {
+        normal: Vec3::Z,
+        geometric_normal: Vec3::Z,
+        tangent: Vec3::X,
+        bitangent: Vec3::Y,
+        t: 1.,
+        face_side,
+        ..Default::default()
+    };
+    (ray, hit_record)
+}
+
+/// Integrals of `pdf` and `eval` over the sphere, summed over a grid of equal-area cells. A grid
+/// converges much faster than random directions on the sharp refraction lobes. Its poles are along
+/// Y, where the cells are thin, away from the plane of incidence the lobes are in.
+fn integrals(material: &dyn Material, ray: &Ray, hit_record: &HitRecord) -> (f64, Color) {
+    const ROWS: u32 = 400;
+    const COLUMNS: u32 = 800;
+    let mut pdf = 0.;
+    let mut eval = Color::ZERO;
+    for row in 0..ROWS {
+        let z = -1. + 2. * (row as f64 + 0.5) / ROWS as f64;
+        let radius = (1. - z * z).sqrt();
+        for column in 0..COLUMNS {
+            let phi = 2. * PI * (column as f64 + 0.5) / COLUMNS as f64;
+            let direction = Vec3::new(radius * phi.cos(), z, radius * phi.sin());
+            let scattered = Ray::new(hit_record.point, direction);
+            pdf += material.pdf(ray, hit_record, &scattered);
+            eval += material.eval(ray, hit_record, &scattered);
+        }
+    }
+    let cell = 4. * PI / (ROWS * COLUMNS) as f64;
+    (pdf * cell, eval * cell)
+}
+
+/// Fraction of `scatter` calls that return a direction, and the mean attenuation, counting
+/// absorbed samples as zero.
+fn scatter_statistics(material: &dyn Material, ray: &Ray, hit_record: &HitRecord) -> (f64, Color) {
+    let mut rng = StdRng::seed_from_u64(73);
+    let mut scattered_count = 0;
+    let mut attenuation = Color::ZERO;
+    for _ in 0..SAMPLES {
+        let Some((scattered, color)) = material.scatter(ray, hit_record, &mut rng) else {
+            continue;
+        };
+        scattered_count += 1;
+        attenuation += color;
+
+        // Each sample's weight is the BSDF over the density of choosing it
+        let pdf = material.pdf(ray, hit_record, &scattered);
+        let eval = material.eval(ray, hit_record, &scattered);
+        assert!(pdf > 0., "sampled a direction of zero density");
+        assert!(
+            (eval / pdf).abs_diff_eq(color, 1e-6 * color.max_element().max(1.)),
+            "eval / pdf = {} but scatter weighs it {color}",
+            eval / pdf
+        );
+    }
+    (
+        scattered_count as f64 / SAMPLES as f64,
+        attenuation / SAMPLES as f64,
+    )
+}
+
+#[test]
+fn pdf_integrates_to_the_fraction_of_samples_kept() {
+    for (name, material, face_side) in materials() {
+        for angle in ANGLES {
+            let (ray, hit_record) = incoming(angle, face_side);
+            let (pdf, _) = integrals(material.as_ref(), &ray, &hit_record);
+            let (kept, _) = scatter_statistics(material.as_ref(), &ray, &hit_record);
+            // Samples leaving on the wrong side of the surface are dropped, so neither is quite 1
+            assert!(
+                (pdf - kept).abs() < 0.01,
+                "{name} at {angle}°: pdf integrates to {pdf}, {kept} of samples kept"
+            );
+        }
+    }
+}
+
+#[test]
+fn eval_integrates_to_the_mean_attenuation() {
+    for (name, material, face_side) in materials() {
+        for angle in ANGLES {
+            let (ray, hit_record) = incoming(angle, face_side);
+            let (_, eval) = integrals(material.as_ref(), &ray, &hit_record);
+            let (_, attenuation) = scatter_statistics(material.as_ref(), &ray, &hit_record);
+            assert!(
+                (eval - attenuation).abs().max_element() < 0.01,
+                "{name} at {angle}°: eval integrates to {eval}, mean attenuation {attenuation}"
+            );
+        }
+    }
+}
+
+#[test]
+fn white_principled_materials_dont_create_energy() {
+    for (name, material) in principled() {
+        for angle in ANGLES {
+            let (ray, hit_record) = incoming(angle, FaceSide::Front);
+            let (_, attenuation) = scatter_statistics(material.as_ref(), &ray, &hit_record);
+            assert!(
+                attenuation.max_element() <= 1.,
+                "{name} at {angle}°: mean attenuation {attenuation}"
+            );
+        }
+    }
+}
+
+fn coated(base: Arc<dyn Material>) -> Coated {
+    Coated::new(base, 1.5)
+        .roughness(Arc::new(SolidColor::new(Color::splat(0.3))))
+        .absorption(Color::new(0.1, 0.3, 0.5), 0.5)
+}
+
+#[test]
+fn coated_eval_matches_the_bounces_under_the_coat() {
+    let coated = coated(Arc::new(Lambertian::new(Color::new(0.8, 0.5, 0.2))));
+    for angle in ANGLES.into_iter().chain([85.]) {
+        let (ray, hit_record) = incoming(angle, FaceSide::Front);
+        let (pdf, eval) = integrals(&coated, &ray, &hit_record);
+
+        let mut rng = StdRng::seed_from_u64(48);
+        let mut kept = 0;
+        let mut attenuation = Color::ZERO;
+        for _ in 0..SAMPLES {
+            if let Some((_, color)) = coated.scatter(&ray, &hit_record, &mut rng) {
+                kept += 1;
+                attenuation += color;
+            }
+        }
+        let kept = kept as f64 / SAMPLES as f64;
+        let attenuation = attenuation / SAMPLES as f64;
+
+        assert!(
+            (pdf - kept).abs() < 0.01,
+            "at {angle}°: pdf integrates to {pdf}, {kept} of samples kept"
+        );
+        assert!(
+            (eval - attenuation).abs().max_element() < 0.01,
+            "at {angle}°: eval integrates to {eval}, mean attenuation {attenuation}"
+        );
+    }
+}
+
+#[test]
+fn coats_over_glossy_bases_are_only_found_by_scattering() {
+    // Light bounces between a glossy base and the coat in no closed form, so rather than get
+    // `eval` wrong the coat is treated like a mirror
+    let coated = coated(Arc::new(Conductor::new(ComplexIor::GOLD, 0.2)));
+    for angle in ANGLES {
+        let (ray, hit_record) = incoming(angle, FaceSide::Front);
+        assert_eq!(
+            integrals(&coated, &ray, &hit_record),
+            (0., Color::ZERO),
+            "at {angle}°"
+        );
+        let mut rng = StdRng::seed_from_u64(48);
+        assert!((0..100).any(|_| coated.scatter(&ray, &hit_record, &mut rng).is_some()));
+    }
+}
+
+#[test]
+fn frosted_glass_disperses_light() {
+    let glass = RoughDielectric::new(Ior::SF11, 0.);
+    let (mut ray, hit_record) = incoming(45., FaceSide::Front);
+    let mut rng = StdRng::seed_from_u64(41);
+    for u in [0., 0.5, 0.99] {
+        let wavelengths = Wavelengths::sample(u);
+        ray.wavelengths = Some(wavelengths);
+        let n = Ior::SF11.at(wavelengths.hero());
+        // Nearly smooth, so refracted rays follow Snell's law
+        let expected = 45f64.to_radians().sin() / n;
+        let refracted = (0..100)
+            .filter_map(|_| glass.scatter(&ray, &hit_record, &mut rng))
+            .map(|(scattered, _)| scattered.direction.normalize())
+            .find(|direction| direction.z < 0.)
+            .expect("some light refracts");
+        assert!(
+            (refracted.x - expected).abs() < 1e-3,
+            "at {} nm: refracted to {refracted}, expected sin {expected}",
+            wavelengths.hero()
+        );
+    }
+
+    // And the medium inside carries the same curve
+    assert_eq!(glass.medium().unwrap().ior, Ior::SF11);
+}
@@ -0,0 +1,109 @@
+//! Checks that the medium stack picks the medium a ray is in, and the index of refraction outside
+//! each surface it reaches, for overlapping objects with priorities: ice floating in water, and a
+//! liquid filling a glass whose inner wall it overlaps.
+
+use ray_tow::material::{Dielectric, Material};
+use ray_tow::medium::{Medium, MediumStack};
+
+const WAVELENGTH: f64 = 550.;
+
+const WATER: usize = 1;
+const ICE: usize = 2;
+const GLASS: usize = 3;
+const LIQUID: usize = 4;
+
+fn medium(ior: f64, priority: u32) -> Medium {
+    Dielectric::new(ior).priority(priority).medium().unwrap()
+}
+
+/// Crosses a surface, after checking the index of refraction outside it, `None` if it's passed
+/// through.
+fn cross(
+    media: &mut MediumStack,
+    id: usize,
+    medium: &Medium,
+    entering: bool,
+    exterior: Option<f64>,
+) {
+    assert_eq!(
+        media.exterior_ior(id, medium, entering, WAVELENGTH),
+        exterior,
+        "crossing {id}, entering {entering}"
+    );
+    media.cross(id, medium.clone(), entering);
+}
+
+#[test]
+fn ice_floats_in_water() {
+    let water = medium(1.33, 1);
+    let ice = medium(1.31, 2);
+
+    // Into the water, then into the ice under the surface and back out
+    let mut media = MediumStack::default();
+    cross(&mut media, WATER, &water, true, Some(1.));
+    cross(&mut media, ICE, &ice, true, Some(1.33));
+    assert_eq!(media.ior(WAVELENGTH), 1.31);
+    cross(&mut media, ICE, &ice, false, Some(1.33));
+    assert_eq!(media.ior(WAVELENGTH), 1.33);
+
+    // Into the ice above the water, through the water's surface inside it and out underwater
+    let mut media = MediumStack::default();
+    cross(&mut media, ICE, &ice, true, Some(1.));
+    cross(&mut media, WATER, &water, true, None);
+    assert_eq!(media.ior(WAVELENGTH), 1.31);
+    cross(&mut media, ICE, &ice, false, Some(1.33));
+    assert_eq!(media.ior(WAVELENGTH), 1.33);
+    cross(&mut media, WATER, &water, false, Some(1.));
+    assert!(media.current().is_none());
+}
+
+#[test]
+fn liquid_fills_its_glass() {
+    let glass = medium(1.5, 1);
+    let liquid = medium(1.33, 2);
+
+    // Through the glass wall into the liquid, whose surface is inside the wall
+    let mut media = MediumStack::default();
+    cross(&mut media, GLASS, &glass, true, Some(1.));
+    cross(&mut media, LIQUID, &liquid, true, Some(1.5));
+    assert_eq!(media.ior(WAVELENGTH), 1.33);
+    // The wall's inner surface is inside the liquid, so it's passed through
+    cross(&mut media, GLASS, &glass, false, None);
+    assert_eq!(media.ior(WAVELENGTH), 1.33);
+
+    // And out through the other side
+    cross(&mut media, GLASS, &glass, true, None);
+    cross(&mut media, LIQUID, &liquid, false, Some(1.5));
+    assert_eq!(media.ior(WAVELENGTH), 1.5);
+    cross(&mut media, GLASS, &glass, false, Some(1.));
+    assert!(media.current().is_none());
+}
+
+#[test]
+fn innermost_of_equal_priorities_wins() {
+    let mut media = MediumStack::default();
+    cross(&mut media, 1, &medium(1.5, 0), true, Some(1.));
+    cross(&mut media, 2, &medium(1.3, 0), true, Some(1.5));
+    assert_eq!(media.ior(WAVELENGTH), 1.3);
+    cross(&mut media, 3, &medium(1.7, 0), true, Some(1.3));
+    assert_eq!(media.ior(WAVELENGTH), 1.7);
+}
+
+#[test]
+fn leaving_an_outer_medium_keeps_the_inner_ones() {
+    let outer = medium(1.5, 0);
+    let middle = medium(1.3, 0);
+    let inner = medium(1.7, 0);
+    let mut media = MediumStack::default();
+    cross(&mut media, 1, &outer, true, Some(1.));
+    cross(&mut media, 2, &middle, true, Some(1.5));
+    cross(&mut media, 3, &inner, true, Some(1.3));
+
+    // Leaving the outer object, its surface overlapping the inner ones, lands in the innermost
+    cross(&mut media, 1, &outer, false, Some(1.7));
+    assert_eq!(media.ior(WAVELENGTH), 1.7);
+    cross(&mut media, 3, &inner, false, Some(1.3));
+    assert_eq!(media.ior(WAVELENGTH), 1.3);
+    cross(&mut media, 2, &middle, false, Some(1.));
+    assert!(media.current().is_none());
+}
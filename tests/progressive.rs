@@ -0,0 +1,59 @@
+//! Checks the passes of progressive rendering used by watch mode against a full render.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use ray_tow::camera::Camera;
+use ray_tow::material::{Dielectric, Lambertian, Metal};
+use ray_tow::raw_image_buffer::RawImageBuffer;
+use ray_tow::shapes::{sphere::Sphere, Shape};
+use ray_tow::{Color, Vec3};
+
+fn scene() -> (Camera, Vec<Shape>) {
+    let world = vec![
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., -100.5, -1.),
+            100.,
+            Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.))),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(-1., 0., -1.),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(1., 0., -1.),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        )),
+    ];
+    let camera = Camera::init()
+        .position(Vec3::new(-2., 2., 1.))
+        .look_at(Vec3::new(0., 0., -1.))
+        .image_width(24)
+        .samples_per_pixel(3)
+        .seed(30)
+        .build();
+    (camera, world)
+}
+
+#[test]
+fn first_pass_matches_render() {
+    let (camera, world) = scene();
+    let cancel = AtomicBool::new(false);
+    let pass = camera.render_pass(&world, 0, &cancel).unwrap();
+    let pass = RawImageBuffer::from_colors(camera.image_width, camera.image_height, &pass);
+    assert_eq!(pass.buf, camera.render(&world).buf);
+
+    // Later passes sample afresh
+    let next = camera.render_pass(&world, 1, &cancel).unwrap();
+    let next = RawImageBuffer::from_colors(camera.image_width, camera.image_height, &next);
+    assert_ne!(next.buf, pass.buf);
+}
+
+#[test]
+fn cancelled_pass_returns_nothing() {
+    let (camera, world) = scene();
+    let cancel = AtomicBool::new(true);
+    assert!(camera.render_pass(&world, 0, &cancel).is_none());
+}
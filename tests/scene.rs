@@ -0,0 +1,116 @@
+#![cfg(feature = "serde")]
+
+//! Checks that mistakes in scene files are reported with the field, and where known the line and
+//! column, at fault.
+
+use ray_tow::scene::{Scene, SceneError};
+
+const MATERIALS: &str = r#"
+[materials.ground]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+"#;
+
+fn load(shapes: &str) -> Result<Scene, SceneError> {
+    Scene::from_toml(&format!("{MATERIALS}{shapes}"))
+}
+
+fn sphere(radius: &str) -> String {
+    format!(
+        r#"
+[[shapes]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = {radius}
+material = "ground"
+"#
+    )
+}
+
+#[test]
+fn valid_scene_loads() {
+    let scene = load(&sphere("1.0")).unwrap();
+    assert_eq!(scene.world.len(), 1);
+}
+
+#[test]
+fn unknown_material_points_at_the_shape() {
+    let err = load(
+        r#"
+[[shapes]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "ground"
+
+[[shapes]]
+type = "triangle"
+vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+material = "grund"
+"#,
+    )
+    .unwrap_err();
+
+    let SceneError::Invalid {
+        location,
+        field,
+        message,
+    } = &err
+    else {
+        panic!("expected an invalid value, got {err}");
+    };
+    assert_eq!(field, "shapes[1].material");
+    assert!(message.contains("`grund`"), "{message}");
+    assert!(message.contains("`ground`"), "{message}");
+    // The second shape's table starts on line 12, after the material and first shape
+    assert_eq!(*location, Some((12, 1)), "{err}");
+    assert!(err.to_string().starts_with("line 12, column 1: "), "{err}");
+}
+
+#[test]
+fn zero_and_negative_radii_are_rejected() {
+    for radius in ["0.0", "-1.0", "inf", "nan"] {
+        let err = load(&sphere(radius)).unwrap_err();
+        assert!(
+            matches!(&err, SceneError::Invalid { field, .. } if field == "shapes[0].radius"),
+            "radius {radius}: {err}"
+        );
+    }
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let err = load(
+        r#"
+[[shapes]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+radus = 2.0
+material = "ground"
+"#,
+    )
+    .unwrap_err();
+    assert!(matches!(err, SceneError::Parse(_)), "{err}");
+    assert!(err.to_string().contains("radus"), "{err}");
+
+    let err = Scene::from_toml("[camera]\nimage_widht = 400\n").unwrap_err();
+    assert!(err.to_string().contains("image_widht"), "{err}");
+    assert!(err.to_string().contains("line 2"), "{err}");
+}
+
+#[test]
+fn metal_fuzz_outside_the_unit_interval_is_rejected() {
+    for fuzz in ["5.0", "-0.1", "[0.1, 0.2, 1.5]"] {
+        let err = Scene::from_toml(&format!(
+            "[materials.mirror]\ntype = \"metal\"\nalbedo = 0.9\nfuzz = {fuzz}\n"
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("fuzz"), "fuzz {fuzz}: {err}");
+        assert!(
+            err.to_string().contains("between 0 and 1"),
+            "fuzz {fuzz}: {err}"
+        );
+    }
+    Scene::from_toml("[materials.mirror]\ntype = \"metal\"\nalbedo = 0.9\nfuzz = 0.3\n").unwrap();
+}
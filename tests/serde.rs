@@ -0,0 +1,89 @@
+#![cfg(feature = "serde")]
+
+use std::sync::Arc;
+
+use ray_tow::camera::{Camera, CameraBuilder};
+use ray_tow::light::SpotLight;
+use ray_tow::material::{Dielectric, DiffuseLight, Lambertian, Metal};
+use ray_tow::scene::Scene;
+use ray_tow::shapes::{sphere::Sphere, Shape};
+use ray_tow::{Color, Vec3};
+
+fn scene() -> (CameraBuilder, Vec<Shape>) {
+    let world = vec![
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., -100.5, -1.),
+            100.,
+            Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.))),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(-1., 0., -1.),
+            0.5,
+            Arc::new(Dielectric::new(1.5)),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(1., 0., -1.),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3)),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., 2., -1.),
+            0.5,
+            Arc::new(DiffuseLight::new(Color::new(4., 4., 4.))),
+        )),
+    ];
+    let camera = Camera::init()
+        .position(Vec3::new(-2., 2., 1.))
+        .look_at(Vec3::new(0., 0., -1.))
+        .focal_length(30e-3)
+        .f_stop(2.8)
+        .image_width(40)
+        .samples_per_pixel(4)
+        .seed(11)
+        .light(SpotLight::new(Vec3::new(0., 3., 0.), -Vec3::Y, Color::ONE, 20.).falloff(5.));
+    (camera, world)
+}
+
+#[test]
+fn json_round_trip_renders_identically() {
+    let (camera, world) = scene();
+    let camera = camera.build();
+
+    let camera_json = serde_json::to_string(&camera).unwrap();
+    let world_json = serde_json::to_string(&world).unwrap();
+    let loaded_camera: Camera = serde_json::from_str(&camera_json).unwrap();
+    let loaded_world: Vec<Shape> = serde_json::from_str(&world_json).unwrap();
+
+    assert_eq!(
+        camera.render(&world).buf,
+        loaded_camera.render(&loaded_world).buf
+    );
+}
+
+#[test]
+fn builder_round_trip_renders_identically() {
+    let (camera, world) = scene();
+
+    let json = serde_json::to_string(&camera).unwrap();
+    let loaded: CameraBuilder = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(
+        camera.build().render(&world).buf,
+        loaded.build().render(&world).buf
+    );
+}
+
+#[test]
+fn scene_file_round_trip_renders_identically() {
+    let (camera, world) = scene();
+    let scene = Scene::new(camera, world);
+
+    let loaded = Scene::from_toml(&scene.to_toml().unwrap()).unwrap();
+
+    let (world, camera) = scene.build();
+    let (loaded_world, loaded_camera) = loaded.build();
+    assert_eq!(
+        camera.render(&world).buf,
+        loaded_camera.render(&loaded_world).buf
+    );
+}
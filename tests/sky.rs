@@ -0,0 +1,84 @@
+//! Checks that the daylight sky and its sun follow the sun's position: where the sun is, how the
+//! sky brightens around it, and how both redden and dim towards sunset.
+
+use ray_tow::background::Background;
+use ray_tow::camera::Camera;
+use ray_tow::light::Light;
+use ray_tow::sky::Sky;
+use ray_tow::Vec3;
+
+#[test]
+fn sun_is_placed_by_elevation_and_azimuth() {
+    let cases = [
+        (90., 0., Vec3::Y),
+        (0., 0., Vec3::NEG_Z),
+        (0., 90., Vec3::X),
+        (30., 180., Vec3::new(0., 0.5, 0.75f64.sqrt())),
+    ];
+    for (elevation, azimuth, expected) in cases {
+        let direction = Sky::new(elevation, azimuth).sun_direction();
+        assert!(
+            direction.abs_diff_eq(expected, 1e-12),
+            "elevation {elevation}, azimuth {azimuth}: {direction}"
+        );
+    }
+}
+
+#[test]
+fn sky_is_brightest_around_the_sun() {
+    let sky = Sky::new(30., 90.);
+    let towards_sun = sky.radiance(Vec3::new(1., 0.6, 0.).normalize());
+    let away = sky.radiance(Vec3::new(-1., 0.6, 0.).normalize());
+    assert!(towards_sun.y > away.y, "{towards_sun} towards, {away} away");
+
+    // A clear sky is blue overhead
+    let zenith = sky.radiance(Vec3::Y);
+    assert!(zenith.z > zenith.x, "{zenith}");
+}
+
+#[test]
+fn sun_reddens_and_dims_as_it_sets() {
+    let sun = |elevation| {
+        let sun = Sky::new(elevation, 0.).sun();
+        sun.color * sun.intensity
+    };
+    let (high, low) = (sun(60.), sun(5.));
+    assert!(
+        low.length() < high.length(),
+        "{low} at sunset, {high} at noon"
+    );
+    assert!(
+        low.x / low.z > high.x / high.z,
+        "{low} at sunset, {high} at noon"
+    );
+
+    // Haze dims it too
+    let hazy = Sky::new(60., 0.).turbidity(8.).sun();
+    assert!((hazy.color * hazy.intensity).length() < high.length());
+}
+
+#[test]
+fn sun_below_the_horizon_keeps_the_sky_finite() {
+    let horizon = Sky::new(0., 0.).sun();
+    for elevation in [-3., -5., -30., -90., 95.] {
+        let sky = Sky::new(elevation, 0.);
+        let sun = sky.sun();
+        assert!(sun.color.is_finite(), "{} at {elevation}°", sun.color);
+        for direction in [Vec3::Y, Vec3::new(1., 0.1, 0.), Vec3::NEG_Y] {
+            let radiance = sky.radiance(direction);
+            assert!(radiance.is_finite(), "{radiance} at {elevation}°");
+        }
+        if elevation < 0. {
+            assert_eq!(sun.color, horizon.color, "at {elevation}°");
+        }
+    }
+}
+
+#[test]
+fn camera_lights_the_world_with_the_sky_sun() {
+    let sky = Sky::new(40., 10.);
+    let camera = Camera::init()
+        .background(Background::Sky(sky.clone()))
+        .build();
+    assert_eq!(camera.lights, vec![Light::Sun(sky.sun())]);
+}
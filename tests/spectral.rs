@@ -0,0 +1,138 @@
+//! Checks that spectral rendering agrees with RGB rendering: colors upsampled to spectra at the
+//! sampled wavelengths come back out as the same color on average, and so do whole renders.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use ray_tow::camera::Camera;
+use ray_tow::material::Lambertian;
+use ray_tow::shapes::{sphere::Sphere, Shape};
+use ray_tow::spectrum::{spectrum_to_rgb, Wavelengths, MAX_WAVELENGTH, MIN_WAVELENGTH};
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 10_000;
+
+const COLORS: [Color; 6] = [
+    Color::ONE,
+    Color::new(0.5, 0.5, 0.5),
+    Color::new(0.8, 0.1, 0.1),
+    Color::new(0.1, 0.7, 0.2),
+    Color::new(0.2, 0.3, 0.9),
+    Color::new(0.9, 0.8, 0.1),
+];
+
+/// Mean over evenly spread wavelength samples of the RGB of `color`'s spectrum, weighted by
+/// `weight`.
+fn mean_rgb(color: Color, mut weight: impl FnMut(&mut Wavelengths) -> Color) -> Color {
+    (0..SAMPLES)
+        .map(|i| {
+            let mut wavelengths = Wavelengths::sample((i as f64 + 0.5) / SAMPLES as f64);
+            let weight = weight(&mut wavelengths);
+            wavelengths.to_rgb(wavelengths.upsample(color) * weight)
+        })
+        .sum::<Color>()
+        / SAMPLES as f64
+}
+
+fn assert_close(actual: Color, expected: Color, tolerance: f64, what: &str) {
+    assert!(
+        actual.abs_diff_eq(expected, tolerance),
+        "{what}: {actual} instead of {expected}"
+    );
+}
+
+#[test]
+fn wavelengths_wrap_around_the_visible_range() {
+    let spacing = (MAX_WAVELENGTH - MIN_WAVELENGTH) / 3.;
+    for u in [0., 0.2, 0.5, 0.7, 0.99] {
+        let wavelengths = Wavelengths::sample(u);
+        let lambda = wavelengths.lambda;
+        assert_eq!(wavelengths.hero(), lambda.x);
+        for i in 0..3 {
+            assert!(
+                (MIN_WAVELENGTH..MAX_WAVELENGTH).contains(&lambda[i]),
+                "u = {u}: {lambda}"
+            );
+            // Each is a third of the range after the one before, wrapping at the end
+            let next = lambda[(i + 1) % 3];
+            let step = (next - lambda[i]).rem_euclid(MAX_WAVELENGTH - MIN_WAVELENGTH);
+            assert!((step - spacing).abs() < 1e-9, "u = {u}: {lambda}");
+        }
+    }
+}
+
+#[test]
+fn white_spectrum_is_white() {
+    assert_close(
+        spectrum_to_rgb(|_| 1.),
+        Color::ONE,
+        0.01,
+        "constant spectrum",
+    );
+    assert_close(
+        spectrum_to_rgb(|_| 0.5),
+        Color::splat(0.5),
+        0.01,
+        "constant spectrum",
+    );
+}
+
+#[test]
+fn upsampled_colors_average_back_to_themselves() {
+    for color in COLORS {
+        let rgb = mean_rgb(color, |_| Color::ONE);
+        // Smits' spectra only approximate saturated colors
+        assert_close(rgb, color, 0.03, "upsampled and back");
+    }
+}
+
+#[test]
+fn terminated_paths_carry_all_the_light_on_average() {
+    for color in COLORS {
+        let rgb = mean_rgb(color, |wavelengths| wavelengths.terminate_secondary());
+        assert_close(rgb, color, 0.03, "hero wavelength only");
+    }
+
+    // Terminating again doesn't weigh the path twice
+    let mut wavelengths = Wavelengths::sample(0.3);
+    assert_eq!(wavelengths.terminate_secondary(), Color::new(3., 0., 0.));
+    assert_eq!(wavelengths.terminate_secondary(), Color::ONE);
+}
+
+/// Mean color of a render of grey and white spheres.
+fn mean_render(spectral: bool) -> Color {
+    let world = vec![
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., -100.5, -1.),
+            100.,
+            Arc::new(Lambertian::new(Color::splat(0.5))),
+        )),
+        Shape::Sphere(Sphere::new(
+            Vec3::new(0., 0., -1.),
+            0.5,
+            Arc::new(Lambertian::new(Color::ONE)),
+        )),
+    ];
+    let camera = Camera::init()
+        .position(Vec3::new(0., 0.5, 1.))
+        .look_at(Vec3::new(0., 0., -1.))
+        .image_width(32)
+        .samples_per_pixel(64)
+        .seed(41)
+        .spectral(spectral)
+        .build();
+    let pixels = camera
+        .render_pass(&world, 0, &AtomicBool::new(false))
+        .unwrap();
+    pixels.iter().sum::<Color>() / pixels.len() as f64
+}
+
+#[test]
+fn spectral_render_matches_rgb_render() {
+    let rgb = mean_render(false);
+    let spectral = mean_render(true);
+    assert!(
+        ((spectral - rgb) / rgb).abs().max_element() < 0.02,
+        "spectral render averages {spectral}, RGB render {rgb}"
+    );
+}
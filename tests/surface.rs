@@ -0,0 +1,168 @@
+//! Checks the shading frame on triangle hits, and that normal maps tilt the shading normal within
+//! it without sending light through the surface or losing the medium behind it.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::hit_record::{HitRecord, Hittable};
+use ray_tow::material::{BumpMap, Dielectric, Lambertian, Material, NormalMap};
+use ray_tow::medium::MediumStack;
+use ray_tow::ray::Ray;
+use ray_tow::shapes::triangle::Triangle;
+use ray_tow::texture::SolidColor;
+use ray_tow::{Color, Vec2, Vec3};
+
+/// The unit right triangle in the XY plane, facing +Z.
+fn triangle(uvs: [Vec2; 3], material: Arc<dyn Material>) -> Triangle {
+    Triangle::new([Vec3::ZERO, Vec3::X, Vec3::Y], material).uvs(uvs)
+}
+
+fn lambertian() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::ONE))
+}
+
+/// A ray straight down onto the triangle, from above or below.
+fn ray(from_above: bool) -> Ray {
+    let z = if from_above { 1. } else { -1. };
+    Ray::new(Vec3::new(0.25, 0.25, z), Vec3::new(0., 0., -z))
+}
+
+fn hit<'a>(triangle: &'a Triangle, ray: &Ray) -> HitRecord<'a> {
+    triangle.hit(ray, 1e-3..f64::INFINITY).unwrap()
+}
+
+fn assert_orthonormal(hit_record: &HitRecord) {
+    let frame = [hit_record.tangent, hit_record.bitangent, hit_record.normal];
+    for (i, a) in frame.iter().enumerate() {
+        assert!((a.length() - 1.).abs() < 1e-12, "{frame:?}");
+        for b in &frame[i + 1..] {
+            assert!(a.dot(*b).abs() < 1e-12, "{frame:?}");
+        }
+    }
+}
+
+#[test]
+fn tangent_frame_follows_the_texture_coordinates() {
+    let standard = [Vec2::ZERO, Vec2::X, Vec2::Y];
+    // u runs towards -X, so the frame is mirrored
+    let mirrored = [Vec2::X, Vec2::ZERO, Vec2::ONE];
+    let cases = [
+        (standard, true, Vec3::Z, Vec3::X, Vec3::Y),
+        (standard, false, Vec3::NEG_Z, Vec3::X, Vec3::Y),
+        (mirrored, true, Vec3::Z, Vec3::NEG_X, Vec3::Y),
+    ];
+    for (uvs, from_above, normal, tangent, bitangent) in cases {
+        let triangle = triangle(uvs, lambertian());
+        let hit_record = hit(&triangle, &ray(from_above));
+        assert_eq!(hit_record.normal, normal);
+        assert_eq!(hit_record.geometric_normal, normal);
+        assert!(
+            hit_record.tangent.abs_diff_eq(tangent, 1e-12)
+                && hit_record.bitangent.abs_diff_eq(bitangent, 1e-12),
+            "{uvs:?}: tangent {}, bitangent {}",
+            hit_record.tangent,
+            hit_record.bitangent
+        );
+        assert_orthonormal(&hit_record);
+    }
+
+    // Skewed coordinates, where dpdu and dpdv aren't perpendicular
+    let triangle = triangle([Vec2::ZERO, Vec2::new(1., 0.5), Vec2::Y], lambertian());
+    let hit_record = hit(&triangle, &ray(true));
+    assert_orthonormal(&hit_record);
+    assert!(hit_record
+        .tangent
+        .abs_diff_eq(hit_record.dpdu.normalize(), 1e-12));
+    assert!(hit_record.bitangent.dot(hit_record.dpdv) > 0.);
+}
+
+/// A normal map of a single normal, tilted by `angle` degrees towards the tangent.
+fn tilted(angle: f64) -> Arc<dyn Material> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let encoded = (Color::new(sin, 0., cos) + 1.) / 2.;
+    Arc::new(NormalMap::new(
+        Arc::new(SolidColor::new(encoded)),
+        lambertian(),
+    ))
+}
+
+fn eval(hit_record: &HitRecord, ray: &Ray, direction: Vec3) -> f64 {
+    let scattered = Ray::new(hit_record.point, direction.normalize());
+    let color = hit_record.material.eval(ray, hit_record, &scattered);
+    assert_eq!(color, Color::splat(color.x));
+    color.x
+}
+
+#[test]
+fn normal_maps_tilt_the_shading_normal_in_the_tangent_frame() {
+    let (sin, cos) = 30f64.to_radians().sin_cos();
+    for (uvs, tilted_normal) in [
+        ([Vec2::ZERO, Vec2::X, Vec2::Y], Vec3::new(sin, 0., cos)),
+        // Mirrored coordinates tilt it the other way, like the texture
+        ([Vec2::X, Vec2::ZERO, Vec2::ONE], Vec3::new(-sin, 0., cos)),
+    ] {
+        let triangle = triangle(uvs, tilted(30.));
+        let ray = ray(true);
+        let hit_record = hit(&triangle, &ray);
+        // Diffuse reflection peaks along the tilted normal
+        let along = eval(&hit_record, &ray, tilted_normal);
+        assert!((along - 1. / PI).abs() < 1e-12, "{along}");
+        let up = eval(&hit_record, &ray, Vec3::Z);
+        assert!((up - cos / PI).abs() < 1e-12, "{up}");
+    }
+}
+
+#[test]
+fn normal_maps_dont_scatter_through_the_surface() {
+    let triangle = triangle([Vec2::ZERO, Vec2::X, Vec2::Y], tilted(80.));
+    let ray = ray(true);
+    let hit_record = hit(&triangle, &ray);
+
+    // Above the shading normal's horizon, but below the surface
+    let below = Vec3::new(1., 0., -0.1);
+    assert_eq!(eval(&hit_record, &ray, below), 0.);
+    // Above both
+    assert!(eval(&hit_record, &ray, Vec3::new(1., 0., 0.1)) > 0.);
+
+    let mut rng = StdRng::seed_from_u64(36);
+    let mut absorbed = 0;
+    for _ in 0..10_000 {
+        match hit_record.material.scatter(&ray, &hit_record, &mut rng) {
+            Some((scattered, _)) => assert!(
+                scattered.direction.z > 0.,
+                "scattered to {}",
+                scattered.direction
+            ),
+            None => absorbed += 1,
+        }
+    }
+    // Tilted this far, a good share of the cosine lobe is below the surface
+    assert!(absorbed > 1000, "{absorbed} absorbed");
+}
+
+#[test]
+fn bumpy_glass_keeps_its_medium() {
+    let glass: Arc<dyn Material> = Arc::new(Dielectric::new(1.5).priority(3));
+    let flat = Arc::new(SolidColor::new(Color::new(0.5, 0.5, 1.)));
+    let wrapped: [(&str, Arc<dyn Material>); 2] = [
+        (
+            "normal map",
+            Arc::new(NormalMap::new(flat.clone(), glass.clone())),
+        ),
+        ("bump map", Arc::new(BumpMap::new(flat, 0.1, glass))),
+    ];
+    for (name, material) in wrapped {
+        // Rays refracting in are then inside the glass, so its surfaces are found from inside
+        let medium = material
+            .medium()
+            .unwrap_or_else(|| panic!("{name} lost the medium"));
+        let mut media = MediumStack::default();
+        media.cross(1, medium, true);
+        let current = media
+            .current()
+            .unwrap_or_else(|| panic!("{name} not entered"));
+        assert_eq!(current.priority, 3, "{name}");
+    }
+}
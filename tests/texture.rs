@@ -0,0 +1,183 @@
+//! Checks image texture lookups on tiny images with known values: wrapping, filtering, and which
+//! mipmap level the ray footprint picks. Also checks that noise is reproducible from its seed and
+//! stays in range.
+
+use std::path::PathBuf;
+
+use image::{GrayImage, Luma};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::texture::{Filter, ImageTexture, Marble, Perlin, Texture, TextureCoords, Wood, Wrap};
+use ray_tow::vectors::random_in_range;
+use ray_tow::{Color, Vec2, Vec3};
+
+/// Save a 4x4 grayscale image with `value(x, y)` at each pixel, rows from the top, and load it
+/// without sRGB decoding so texels are the values themselves.
+fn image(name: &str, value: impl Fn(u32, u32) -> u8) -> ImageTexture {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("ray-tow-{}-{name}.png", std::process::id()));
+    GrayImage::from_fn(4, 4, |x, y| Luma([value(x, y)]))
+        .save(&path)
+        .unwrap();
+    let texture = ImageTexture::load_linear(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    texture
+}
+
+/// Pixels numbered 0 to 15 in reading order, scaled by 16.
+fn numbered(name: &str) -> ImageTexture {
+    image(name, |x, y| ((y * 4 + x) * 16) as u8)
+}
+
+fn lookup(texture: &ImageTexture, u: f64, v: f64, uv_footprint: f64) -> f64 {
+    let coords = TextureCoords {
+        uv_footprint: Vec2::splat(uv_footprint),
+        ..TextureCoords::new(Vec2::new(u, v), Vec3::ZERO)
+    };
+    let color = texture.value(&coords);
+    assert_eq!(color, Color::splat(color.x), "gray images stay gray");
+    color.x
+}
+
+fn assert_texel(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected / 255.).abs() < 1e-6,
+        "{} instead of {expected}",
+        actual * 255.
+    );
+}
+
+#[test]
+fn wrap_modes_outside_the_image() {
+    // Centers of the pixels in the top row are at v = 0.875 and u = 0.125, 0.375, ...
+    let cases = [
+        // Inside, every mode agrees
+        (0.375, [16., 16., 16.]),
+        // One pixel left of the image
+        (-0.125, [48., 0., 0.]),
+        // One pixel right of the image
+        (1.125, [0., 48., 48.]),
+        // Two pixels left of the image
+        (-0.375, [32., 0., 16.]),
+        // Past the mirrored copy, back to the original
+        (-1.375, [32., 0., 32.]),
+    ];
+    let texture = numbered("wrap").filter(Filter::Nearest);
+    for (u, expected) in cases {
+        for (wrap, expected) in [Wrap::Repeat, Wrap::Clamp, Wrap::Mirror]
+            .into_iter()
+            .zip(expected)
+        {
+            let texture = texture.clone().wrap(wrap);
+            let value = lookup(&texture, u, 0.875, 0.);
+            assert!(
+                (value * 255. - expected).abs() < 1e-4,
+                "{wrap:?} at u = {u}: {} instead of {expected}",
+                value * 255.
+            );
+        }
+    }
+
+    // Rows wrap the same way, with v running up the image
+    let texture = texture.wrap(Wrap::Mirror);
+    assert_texel(lookup(&texture, 0.125, 1.125, 0.), 0.);
+    assert_texel(lookup(&texture, 0.125, -0.125, 0.), 192.);
+}
+
+#[test]
+fn bilinear_blends_the_four_closest_pixels() {
+    let texture = numbered("bilinear")
+        .filter(Filter::Bilinear)
+        .wrap(Wrap::Clamp);
+    // At a pixel center, just that pixel
+    assert_texel(lookup(&texture, 0.375, 0.625, 0.), 80.);
+    // Halfway between two pixels in a row
+    assert_texel(lookup(&texture, 0.25, 0.875, 0.), 8.);
+    // Between four pixels
+    assert_texel(lookup(&texture, 0.25, 0.75, 0.), 40.);
+    // A quarter of the way along a column
+    assert_texel(lookup(&texture, 0.625, 0.8125, 0.), 48.);
+}
+
+#[test]
+fn footprint_picks_the_mipmap_level() {
+    // A checkerboard on the left half only, so the 2x2 level is half white on the left and the 1x1
+    // level a quarter white
+    let texture = image(
+        "mipmap",
+        |x, y| if x < 2 && (x + y) % 2 == 0 { 255 } else { 0 },
+    )
+    .wrap(Wrap::Clamp);
+    let at_corner = |footprint_texels: f64| lookup(&texture, 0.125, 0.875, footprint_texels / 4.);
+
+    // Footprints up to a texel read the full image
+    assert_texel(at_corner(0.), 255.);
+    assert_texel(at_corner(1.), 255.);
+    // Two texels read the 2x2 level, four the 1x1 level
+    assert_texel(at_corner(2.), 127.5);
+    assert_texel(at_corner(4.), 63.75);
+    // Between levels they are blended
+    assert_texel(at_corner(2f64.sqrt()), 0.5 * (255. + 127.5));
+    // Beyond the last level, it's used as is
+    assert_texel(at_corner(100.), 63.75);
+}
+
+/// Random points spread over many lattice cells, including negative ones.
+fn points() -> impl Iterator<Item = Vec3> {
+    let mut rng = StdRng::seed_from_u64(9);
+    (0..10_000).map(move |_| random_in_range(-50.0..50., &mut rng))
+}
+
+#[test]
+fn noise_is_reproducible_from_its_seed() {
+    let (a, b, other) = (Perlin::new(3), Perlin::new(3), Perlin::new(4));
+    let mut differs = false;
+    for point in points().take(100) {
+        assert_eq!(a.noise(point), b.noise(point));
+        assert_eq!(a.turbulence(point, 7), b.turbulence(point, 7));
+        differs |= a.noise(point) != other.noise(point);
+    }
+    assert!(differs, "seeds 3 and 4 give the same noise");
+
+    // Textures rebuilt from the seed alone match too
+    let coords = TextureCoords::new(Vec2::ZERO, Vec3::new(0.3, 1.7, -2.2));
+    let wood = |seed| Wood::new(seed, 6., Color::ONE, Color::ZERO).value(&coords);
+    assert_eq!(wood(Perlin::new(5).seed()), wood(5));
+}
+
+#[test]
+fn noise_stays_in_range() {
+    let perlin = Perlin::new(11);
+    let octaves = 7;
+    // Layers halve in amplitude, so sums stay below twice the first layer
+    let bound = 2. - 0.5f64.powi(octaves as i32 - 1);
+    let mut spread = (f64::INFINITY, f64::NEG_INFINITY);
+    for point in points() {
+        let noise = perlin.noise(point);
+        assert!(noise.abs() <= 1., "noise {noise} at {point}");
+        spread = (spread.0.min(noise), spread.1.max(noise));
+
+        let fbm = perlin.fbm(point, octaves);
+        assert!(fbm.abs() <= bound, "fbm {fbm} at {point}");
+        let turbulence = perlin.turbulence(point, octaves);
+        assert!(
+            (0. ..=bound).contains(&turbulence),
+            "turbulence {turbulence} at {point}"
+        );
+    }
+    // Not stuck at zero either
+    assert!(spread.0 < -0.3 && spread.1 > 0.3, "noise spans {spread:?}");
+
+    // Zero on the lattice
+    assert_eq!(perlin.noise(Vec3::new(3., -7., 12.)), 0.);
+
+    // Textures blend between their two colors
+    let marble = Marble::new(11, 4., Color::ONE, Color::ZERO);
+    for point in points().take(1000) {
+        let color = marble.value(&TextureCoords::new(Vec2::ZERO, point));
+        assert!(
+            color.cmpge(Color::ZERO).all() && color.cmple(Color::ONE).all(),
+            "marble {color} at {point}"
+        );
+    }
+}
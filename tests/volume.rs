@@ -0,0 +1,134 @@
+//! Checks constant-density volumes and fog: that light gets through them unscattered as the
+//! Beer–Lambert law says, and that volumes which only scatter don't lose or create light.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use ray_tow::background::Background;
+use ray_tow::camera::{Camera, CameraBuilder};
+use ray_tow::hit_record::Hittable;
+use ray_tow::material::{DiffuseLight, Lambertian};
+use ray_tow::medium::{sample_flight, Flight, Fog};
+use ray_tow::ray::Ray;
+use ray_tow::shapes::constant_medium::ConstantMedium;
+use ray_tow::shapes::{sphere::Sphere, Shape};
+use ray_tow::{Color, Vec3};
+
+const SAMPLES: u32 = 100_000;
+
+/// A volume filling the unit sphere at the origin.
+fn volume(density: f64, albedo: Color) -> Shape {
+    let boundary = Shape::Sphere(Sphere::new(
+        Vec3::ZERO,
+        1.,
+        Arc::new(Lambertian::new(Color::ONE)),
+    ));
+    Shape::ConstantMedium(ConstantMedium::new(boundary, density, albedo))
+}
+
+/// A camera at `z` on the Z axis, looking down it through a narrow field of view, in front of a
+/// white background.
+fn camera(z: f64) -> CameraBuilder {
+    Camera::init()
+        .position(Vec3::new(0., 0., z))
+        .look_at(Vec3::ZERO)
+        .image_width(9)
+        .image_height(9)
+        .samples_per_pixel(16)
+        .max_depth(100)
+        .seed(46)
+        .background(Background::Solid { color: Color::ONE })
+}
+
+fn render(camera: CameraBuilder, world: &[Shape]) -> Vec<Color> {
+    camera
+        .build()
+        .render_pass(&world.to_vec(), 0, &AtomicBool::new(false))
+        .unwrap()
+}
+
+fn mean(pixels: &[Color]) -> Color {
+    pixels.iter().sum::<Color>() / pixels.len() as f64
+}
+
+#[test]
+fn rays_pass_through_volumes_unscattered_as_beer_lambert_says() {
+    let density = 0.8;
+    let shape = volume(density, Color::new(0.9, 0.5, 0.1));
+    let mut rng = StdRng::seed_from_u64(46);
+    for offset in [0., 0.5, 0.9] {
+        let ray = Ray::new(Vec3::new(offset, 0., 5.), Vec3::NEG_Z);
+        let entry = shape.hit(&ray, 1e-3..f64::INFINITY).unwrap();
+
+        // The surface itself is invisible
+        let (through, attenuation) = entry.material.scatter(&ray, &entry, &mut rng).unwrap();
+        assert_eq!(through.direction, ray.direction);
+        assert_eq!(attenuation, Color::ONE);
+        let exit = shape.hit(&through, 1e-3..f64::INFINITY).unwrap();
+        let chord = exit.t;
+        assert!((chord - 2. * (1. - offset * offset).sqrt()).abs() < 1e-9);
+
+        let medium = entry.material.medium().unwrap();
+        assert!((medium.absorption + medium.scattering).abs_diff_eq(Color::splat(density), 1e-12));
+        let passed = (0..SAMPLES)
+            .filter(|_| {
+                matches!(
+                    sample_flight(medium.absorption, medium.scattering, chord, &mut rng),
+                    Flight::Passed { .. }
+                )
+            })
+            .count() as f64
+            / SAMPLES as f64;
+        let expected = (-density * chord).exp();
+        assert!(
+            (passed - expected).abs() < 0.01,
+            "offset {offset}: {passed} passed unscattered, expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn absorbing_volumes_dim_the_background_behind_them() {
+    // The view is narrow enough that every pixel sees through close to the middle
+    let pixels = render(camera(5.), &[volume(0.7, Color::ZERO)]);
+    let expected = (-0.7f64 * 2.).exp();
+    assert!(
+        mean(&pixels).abs_diff_eq(Color::splat(expected), 0.01),
+        "{} instead of {expected}",
+        mean(&pixels)
+    );
+}
+
+#[test]
+fn white_volumes_in_a_white_furnace_disappear() {
+    // Light scatters around inside, but none of it is lost
+    let pixels = render(camera(5.), &[volume(1.5, Color::ONE)]);
+    assert!(
+        mean(&pixels).abs_diff_eq(Color::ONE, 0.02),
+        "{}",
+        mean(&pixels)
+    );
+}
+
+#[test]
+fn fog_dims_what_is_behind_it_by_distance() {
+    let light = Shape::Sphere(Sphere::new(
+        Vec3::ZERO,
+        1.,
+        Arc::new(DiffuseLight::new(Color::ONE)),
+    ));
+    // The light's surface is 4 away
+    let pixels = render(camera(5.).fog(Fog::new(0.2, Color::ZERO)), &[light]);
+    let expected = (-0.2f64 * 4.).exp();
+    assert!(
+        mean(&pixels).abs_diff_eq(Color::splat(expected), 0.01),
+        "{} instead of {expected}",
+        mean(&pixels)
+    );
+
+    // The fog ends where the scene does, so the background isn't dimmed
+    let pixels = render(camera(5.).fog(Fog::new(0.2, Color::ZERO)), &[]);
+    assert_eq!(mean(&pixels), Color::ONE);
+}